@@ -0,0 +1,5 @@
+-- Add amenities to properties and required amenities to contacts
+ALTER TABLE properties ADD COLUMN amenities JSONB NOT NULL DEFAULT '[]';
+ALTER TABLE contacts ADD COLUMN required_amenities JSONB NOT NULL DEFAULT '[]';
+
+CREATE INDEX idx_properties_amenities_gin ON properties USING GIN(amenities);
@@ -0,0 +1,5 @@
+-- Enable indexed radius searches on property coordinates without requiring PostGIS
+CREATE EXTENSION IF NOT EXISTS cube;
+CREATE EXTENSION IF NOT EXISTS earthdistance;
+
+CREATE INDEX idx_properties_earth_location ON properties USING GIST (ll_to_earth(lat, lon));
@@ -0,0 +1,3 @@
+-- Optional contact details, validated by the API on insert
+ALTER TABLE contacts ADD COLUMN email VARCHAR;
+ALTER TABLE contacts ADD COLUMN phone VARCHAR;
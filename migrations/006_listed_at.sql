@@ -0,0 +1,4 @@
+-- Track when each property was listed so new listings can be boosted
+ALTER TABLE properties ADD COLUMN listed_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+CREATE INDEX idx_properties_listed_at ON properties(listed_at);
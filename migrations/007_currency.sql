@@ -0,0 +1,3 @@
+-- Currency of prices and budgets as ISO 4217 codes; NULL means the configured base currency
+ALTER TABLE properties ADD COLUMN currency VARCHAR(3);
+ALTER TABLE contacts ADD COLUMN currency VARCHAR(3);
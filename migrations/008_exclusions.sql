@@ -0,0 +1,3 @@
+-- Areas and property types a contact never wants to see
+ALTER TABLE contacts ADD COLUMN excluded_locations JSONB NOT NULL DEFAULT '[]';
+ALTER TABLE contacts ADD COLUMN excluded_property_types JSONB NOT NULL DEFAULT '[]';
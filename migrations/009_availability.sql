@@ -0,0 +1,5 @@
+-- Listing status; only available properties are recommended by default
+ALTER TABLE properties ADD COLUMN availability VARCHAR NOT NULL DEFAULT 'available'
+    CHECK (availability IN ('available', 'under_offer', 'sold', 'rented'));
+
+CREATE INDEX idx_properties_availability ON properties(availability);
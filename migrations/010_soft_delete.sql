@@ -0,0 +1,11 @@
+-- Audit timestamps and soft deletion; deleted rows stay referenced by past recommendations
+ALTER TABLE properties ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE properties ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE properties ADD COLUMN deleted_at TIMESTAMPTZ;
+
+ALTER TABLE contacts ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE contacts ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE contacts ADD COLUMN deleted_at TIMESTAMPTZ;
+
+CREATE INDEX idx_properties_not_deleted ON properties(id) WHERE deleted_at IS NULL;
+CREATE INDEX idx_contacts_not_deleted ON contacts(id) WHERE deleted_at IS NULL;
@@ -0,0 +1,2 @@
+-- Optional upper bound on rooms; NULL means no limit
+ALTER TABLE contacts ADD COLUMN max_rooms INTEGER;
@@ -0,0 +1,29 @@
+use actix_web::{web, HttpResponse, Result};
+use crate::config::FeatureFlagsUpdate;
+use crate::middleware::reject_dry_run_write;
+use crate::services::RecommendationService;
+
+pub async fn get_feature_flags(
+    service: web::Data<RecommendationService>,
+) -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(service.feature_flags()))
+}
+
+pub async fn update_feature_flags(
+    request: web::Json<FeatureFlagsUpdate>,
+    service: web::Data<RecommendationService>,
+) -> Result<HttpResponse> {
+    if let Some(response) = reject_dry_run_write() {
+        return Ok(response);
+    }
+
+    Ok(HttpResponse::Ok().json(service.update_feature_flags(&request)))
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/admin")
+            .route("/flags", web::get().to(get_feature_flags))
+            .route("/flags", web::put().to(update_feature_flags))
+    );
+}
@@ -1,6 +1,7 @@
 use actix_web::{web, HttpResponse, Result};
-use crate::services::ComparisonService;
+use crate::services::{ComparisonService, RecommendationService};
 use crate::api::recommendations::ErrorResponse;
+use crate::models::{ContactComparisonRequest, MAX_PROPERTIES_PER_CONTACT_COMPARISON};
 use serde::Deserialize;
 
 #[derive(Deserialize)]
@@ -22,9 +23,45 @@ pub async fn compare_properties(
     }
 }
 
+pub async fn compare_for_contact(
+    request: web::Json<ContactComparisonRequest>,
+    service: web::Data<ComparisonService>,
+    recommendation_service: web::Data<RecommendationService>,
+) -> Result<HttpResponse> {
+    let req = request.into_inner();
+
+    let weights = match req.resolve_weights(recommendation_service.default_weights()) {
+        Ok(weights) => weights,
+        Err(e) => return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Invalid weights".to_string(),
+            message: e,
+        })),
+    };
+
+    if req.property_ids.is_empty() || req.property_ids.len() > MAX_PROPERTIES_PER_CONTACT_COMPARISON {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Invalid property ids".to_string(),
+            message: format!("Provide between 1 and {} property ids", MAX_PROPERTIES_PER_CONTACT_COMPARISON),
+        }));
+    }
+
+    match service.compare_for_contact(req.contact_id, &req.property_ids, weights).await {
+        Ok(Some(comparison)) => Ok(HttpResponse::Ok().json(comparison)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(ErrorResponse {
+            error: "Not found".to_string(),
+            message: format!("Contact {} does not exist", req.contact_id),
+        })),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(ErrorResponse {
+            error: "Failed to compare properties for contact".to_string(),
+            message: e.to_string(),
+        })),
+    }
+}
+
 pub fn configure_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/comparisons")
             .route("/properties", web::get().to(compare_properties))
+            .route("/for-contact", web::post().to(compare_for_contact))
     );
 }
@@ -0,0 +1,179 @@
+use actix_web::{web, HttpResponse, Result};
+use crate::db::Repository;
+use crate::middleware::reject_dry_run_write;
+use crate::models::{ContactValidationError, CreateContactRequest, FieldError, UpdateContactRequest};
+use crate::api::recommendations::ErrorResponse;
+use crate::services::RecommendationService;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct ValidationErrorResponse {
+    pub error: String,
+    pub message: String,
+    pub fields: Vec<FieldError>,
+}
+
+/// A budget in a currency without a configured rate could never be compared
+/// with listing prices, so it is rejected like any other invalid field.
+fn check_currency(service: &RecommendationService, currency: Option<&str>) -> Option<HttpResponse> {
+    let currency = currency.filter(|code| !service.currency().supports(code))?;
+    Some(HttpResponse::BadRequest().json(ValidationErrorResponse {
+        error: "Validation failed".to_string(),
+        message: "invalid contact fields: currency".to_string(),
+        fields: vec![FieldError {
+            field: "currency".to_string(),
+            message: format!("No exchange rate is configured for {}", currency),
+        }],
+    }))
+}
+
+pub async fn create_contact(
+    request: web::Json<CreateContactRequest>,
+    repository: web::Data<Repository>,
+    service: web::Data<RecommendationService>,
+) -> Result<HttpResponse> {
+    if let Some(response) = reject_dry_run_write() {
+        return Ok(response);
+    }
+
+    let contact = request.into_inner().into_contact();
+    if let Some(response) = check_currency(&service, contact.currency.as_deref()) {
+        return Ok(response);
+    }
+
+    match repository.create_contact(&contact).await {
+        Ok(created) => Ok(HttpResponse::Created().json(created)),
+        Err(e) => match e.downcast_ref::<ContactValidationError>() {
+            Some(validation) => Ok(HttpResponse::BadRequest().json(ValidationErrorResponse {
+                error: "Validation failed".to_string(),
+                message: validation.to_string(),
+                fields: validation.errors.clone(),
+            })),
+            None => Ok(HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to create contact".to_string(),
+                message: e.to_string(),
+            })),
+        },
+    }
+}
+
+pub async fn update_contact(
+    path: web::Path<i32>,
+    request: web::Json<UpdateContactRequest>,
+    repository: web::Data<Repository>,
+    service: web::Data<RecommendationService>,
+) -> Result<HttpResponse> {
+    let contact_id = path.into_inner();
+
+    if let Some(response) = reject_dry_run_write() {
+        return Ok(response);
+    }
+
+    let request = request.into_inner();
+    let currency = request.preferences.as_ref().and_then(|preferences| preferences.currency.as_ref());
+    if let Some(response) = check_currency(&service, currency.and_then(Option::as_deref)) {
+        return Ok(response);
+    }
+
+    match repository.update_contact(contact_id, request).await {
+        Ok(Some(updated)) => {
+            // Cached results were scored against the old preferences. Dropping only
+            // the contact_{id}_ keys is not enough: every property_{id}_ entry
+            // embeds a score for each active contact, this one included.
+            service.invalidate_cache();
+            Ok(HttpResponse::Ok().json(updated))
+        }
+        Ok(None) => Ok(HttpResponse::NotFound().json(ErrorResponse {
+            error: "Not found".to_string(),
+            message: format!("Contact {} does not exist", contact_id),
+        })),
+        Err(e) => match e.downcast_ref::<ContactValidationError>() {
+            Some(validation) => Ok(HttpResponse::BadRequest().json(ValidationErrorResponse {
+                error: "Validation failed".to_string(),
+                message: validation.to_string(),
+                fields: validation.errors.clone(),
+            })),
+            None => Ok(HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Failed to update contact".to_string(),
+                message: e.to_string(),
+            })),
+        },
+    }
+}
+
+pub async fn delete_contact(
+    path: web::Path<i32>,
+    repository: web::Data<Repository>,
+    service: web::Data<RecommendationService>,
+) -> Result<HttpResponse> {
+    let contact_id = path.into_inner();
+
+    if let Some(response) = reject_dry_run_write() {
+        return Ok(response);
+    }
+
+    match repository.soft_delete_contact(contact_id).await {
+        Ok(true) => {
+            service.invalidate_cache();
+            Ok(HttpResponse::NoContent().finish())
+        }
+        Ok(false) => Ok(HttpResponse::NotFound().json(ErrorResponse {
+            error: "Not found".to_string(),
+            message: format!("Contact {} does not exist", contact_id),
+        })),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(ErrorResponse {
+            error: "Failed to delete contact".to_string(),
+            message: e.to_string(),
+        })),
+    }
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/contacts")
+            .route("", web::post().to(create_contact))
+            .route("/{contact_id}", web::patch().to(update_contact))
+            .route("/{contact_id}", web::delete().to(delete_contact))
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FeatureFlags;
+    use crate::test_support;
+    use actix_web::{http::StatusCode, test, App};
+
+    #[actix_web::test]
+    async fn budgets_in_currencies_without_a_rate_are_rejected() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::from(test_support::repository()))
+                .app_data(web::Data::new(test_support::recommendation_service(FeatureFlags::default())))
+                .configure(configure_routes),
+        )
+        .await;
+
+        let create = test::TestRequest::post()
+            .uri("/contacts")
+            .set_json(serde_json::json!({
+                "name": "Amina Benali",
+                "preferences": {
+                    "min_budget": 1000, "max_budget": 2000, "currency": "GBP",
+                    "preferred_locations": [], "property_types": ["apartment"],
+                    "min_area_sqm": 80, "max_area_sqm": 140, "min_rooms": 3
+                }
+            }))
+            .to_request();
+        let response = test::call_service(&app, create).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(response).await;
+        assert_eq!(body["fields"][0]["field"], "currency");
+
+        let update = test::TestRequest::patch()
+            .uri("/contacts/1")
+            .set_json(serde_json::json!({"preferences": {"currency": "GBP"}}))
+            .to_request();
+        assert_eq!(test::call_service(&app, update).await.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,6 +1,9 @@
 pub mod recommendations;
 pub mod comparisons;
 pub mod quotes;
+pub mod contacts;
+pub mod admin;
+pub mod properties;
 
 use actix_web::web;
 
@@ -8,4 +11,7 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
     recommendations::configure_routes(cfg);
     comparisons::configure_routes(cfg);
     quotes::configure_routes(cfg);
+    contacts::configure_routes(cfg);
+    admin::configure_routes(cfg);
+    properties::configure_routes(cfg);
 }
@@ -0,0 +1,143 @@
+use actix_web::{web, HttpResponse, Result};
+use crate::db::Repository;
+use crate::middleware::reject_dry_run_write;
+use crate::models::{PropertyAvailability, PropertyFilter, PropertySearchResponse, UpdateAvailabilityRequest};
+use crate::services::{ComparisonService, RecommendationService};
+use crate::services::comparison::MAX_SIMILAR_PROPERTIES;
+use crate::api::recommendations::ErrorResponse;
+
+pub async fn search_properties(
+    query: web::Query<PropertyFilter>,
+    repository: web::Data<Repository>,
+) -> Result<HttpResponse> {
+    let filter = query.into_inner();
+
+    if let Err(e) = filter.validate() {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Invalid filter".to_string(),
+            message: e,
+        }));
+    }
+
+    match repository.search_properties(&filter).await {
+        Ok((properties, total_count)) => Ok(HttpResponse::Ok().json(PropertySearchResponse {
+            properties,
+            total_count,
+            page: filter.page(),
+            page_size: filter.page_size(),
+        })),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(ErrorResponse {
+            error: "Failed to search properties".to_string(),
+            message: e.to_string(),
+        })),
+    }
+}
+
+pub async fn update_availability(
+    path: web::Path<i32>,
+    request: web::Json<UpdateAvailabilityRequest>,
+    repository: web::Data<Repository>,
+    service: web::Data<RecommendationService>,
+) -> Result<HttpResponse> {
+    let property_id = path.into_inner();
+    let availability = request.availability;
+
+    if let Some(response) = reject_dry_run_write() {
+        return Ok(response);
+    }
+
+    match repository.update_property_availability(property_id, availability).await {
+        Ok(Some(previous)) => {
+            if previous != availability {
+                // Cached recommendations may still list the property under its old status
+                service.invalidate_cache();
+                if availability == PropertyAvailability::UnderOffer {
+                    log::info!("Property {} is now under offer (was {})", property_id, previous.as_str());
+                }
+            }
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "property_id": property_id,
+                "previous": previous,
+                "availability": availability,
+            })))
+        }
+        Ok(None) => Ok(HttpResponse::NotFound().json(ErrorResponse {
+            error: "Not found".to_string(),
+            message: format!("Property {} does not exist", property_id),
+        })),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(ErrorResponse {
+            error: "Failed to update availability".to_string(),
+            message: e.to_string(),
+        })),
+    }
+}
+
+pub async fn delete_property(
+    path: web::Path<i32>,
+    repository: web::Data<Repository>,
+    service: web::Data<RecommendationService>,
+) -> Result<HttpResponse> {
+    let property_id = path.into_inner();
+
+    if let Some(response) = reject_dry_run_write() {
+        return Ok(response);
+    }
+
+    match repository.soft_delete_property(property_id).await {
+        Ok(true) => {
+            service.invalidate_cache();
+            Ok(HttpResponse::NoContent().finish())
+        }
+        Ok(false) => Ok(HttpResponse::NotFound().json(ErrorResponse {
+            error: "Not found".to_string(),
+            message: format!("Property {} does not exist", property_id),
+        })),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(ErrorResponse {
+            error: "Failed to delete property".to_string(),
+            message: e.to_string(),
+        })),
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct SimilarQuery {
+    pub k: Option<usize>,
+}
+
+pub async fn get_similar_properties(
+    path: web::Path<i32>,
+    query: web::Query<SimilarQuery>,
+    service: web::Data<ComparisonService>,
+) -> Result<HttpResponse> {
+    let property_id = path.into_inner();
+    let k = query.k.unwrap_or(10);
+
+    if k == 0 || k > MAX_SIMILAR_PROPERTIES {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Invalid k".to_string(),
+            message: format!("k must be between 1 and {}", MAX_SIMILAR_PROPERTIES),
+        }));
+    }
+
+    match service.find_similar(property_id, k).await {
+        Ok(Some(similar)) => Ok(HttpResponse::Ok().json(similar)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(ErrorResponse {
+            error: "Not found".to_string(),
+            message: format!("Property {} does not exist", property_id),
+        })),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(ErrorResponse {
+            error: "Failed to find similar properties".to_string(),
+            message: e.to_string(),
+        })),
+    }
+}
+
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/properties")
+            .route("", web::get().to(search_properties))
+            .route("/{property_id}", web::delete().to(delete_property))
+            .route("/{property_id}/availability", web::put().to(update_availability))
+            .route("/{property_id}/similar", web::get().to(get_similar_properties))
+    );
+}
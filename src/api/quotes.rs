@@ -1,10 +1,24 @@
 use actix_web::{web, HttpResponse, Result};
 use crate::services::QuoteService;
-use crate::services::quote::{QuoteRequest, ComparisonQuoteRequest};
+use crate::services::quote::{QuoteRequest, ComparisonQuoteRequest, QuoteOptions, AffordabilityOptions, QuoteError};
 use crate::api::recommendations::ErrorResponse;
+use crate::utils::pdf::generate_itemized_quote_pdf;
 use serde::Deserialize;
 use chrono;
 
+/// Maps a quote service failure to 404/400 when it is a `QuoteError`, 500 otherwise.
+fn quote_error_response(e: anyhow::Error, error: &str) -> HttpResponse {
+    let body = ErrorResponse {
+        error: error.to_string(),
+        message: e.to_string(),
+    };
+    match e.downcast_ref::<QuoteError>() {
+        Some(QuoteError::NotFound(_)) => HttpResponse::NotFound().json(body),
+        Some(QuoteError::InvalidInput(_)) => HttpResponse::BadRequest().json(body),
+        None => HttpResponse::InternalServerError().json(body),
+    }
+}
+
 pub async fn generate_quote(
     request: web::Json<QuoteRequest>,
     service: web::Data<QuoteService>,
@@ -37,6 +51,59 @@ pub async fn generate_comparison_quote(
     }
 }
 
+pub async fn generate_quote_pdf(
+    path: web::Path<i32>,
+    query: web::Query<QuoteOptions>,
+    service: web::Data<QuoteService>,
+) -> Result<HttpResponse> {
+    let property_id = path.into_inner();
+
+    let quote = match service.build_quote(property_id, query.into_inner()).await {
+        Ok(quote) => quote,
+        Err(e) => return Ok(quote_error_response(e, "Failed to build quote")),
+    };
+
+    match generate_itemized_quote_pdf(&quote) {
+        Ok(pdf_bytes) => Ok(HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header((
+                "Content-Disposition",
+                format!("attachment; filename=\"quote_{}.pdf\"", property_id),
+            ))
+            .body(pdf_bytes)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(ErrorResponse {
+            error: "Failed to render quote PDF".to_string(),
+            message: e.to_string(),
+        })),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AffordabilityQuery {
+    pub contact_id: i32,
+    pub property_id: i32,
+    pub interest_rate: Option<f64>,
+    pub term_years: Option<i32>,
+    pub down_payment_percentage: Option<f64>,
+}
+
+pub async fn get_affordability(
+    query: web::Query<AffordabilityQuery>,
+    service: web::Data<QuoteService>,
+) -> Result<HttpResponse> {
+    let query = query.into_inner();
+    let options = AffordabilityOptions {
+        interest_rate: query.interest_rate,
+        term_years: query.term_years,
+        down_payment_percentage: query.down_payment_percentage,
+    };
+
+    match service.affordability(query.contact_id, query.property_id, options).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(report)),
+        Err(e) => Ok(quote_error_response(e, "Failed to analyze affordability")),
+    }
+}
+
 #[derive(Deserialize)]
 pub struct RecommendationQuoteQuery {
     pub property_id: i32,
@@ -63,6 +130,26 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
         web::scope("/quotes")
             .route("/generate", web::post().to(generate_quote))
             .route("/comparison", web::post().to(generate_comparison_quote))
+            .route("/affordability", web::get().to(get_affordability))
+            .route("/{property_id}.pdf", web::get().to(generate_quote_pdf))
             // .route("/recommendations", web::get().to(generate_recommendation_quote))
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+
+    #[test]
+    fn quote_errors_map_to_client_statuses() {
+        let not_found = quote_error_response(QuoteError::NotFound("Property").into(), "Failed");
+        assert_eq!(not_found.status(), StatusCode::NOT_FOUND);
+
+        let invalid = quote_error_response(QuoteError::InvalidInput("bad".to_string()).into(), "Failed");
+        assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+        let other = quote_error_response(anyhow::anyhow!("connection reset"), "Failed");
+        assert_eq!(other.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -1,6 +1,53 @@
 use actix_web::{web, HttpResponse, Result};
-use crate::services::recommendation::RecommendationService;
+use futures_util::StreamExt;
+use crate::services::recommendation::{RecommendationService, MAX_CONTACTS_PER_BATCH};
 use crate::models::*;
+use crate::config::ScoringWeights;
+
+/// Rejects currencies without a configured exchange rate before any work is done.
+fn check_currency(service: &RecommendationService, currency: Option<&str>) -> Option<HttpResponse> {
+    let currency = currency?;
+    if service.currency().supports(currency) {
+        return None;
+    }
+    Some(HttpResponse::BadRequest().json(ErrorResponse {
+        error: "Unsupported currency".to_string(),
+        message: format!("No exchange rate configured for {}", currency),
+    }))
+}
+
+/// Percentiles are fractions: `top_percentile` must keep something and
+/// `score_threshold_percentile` must lie within 0–1.
+fn check_percentiles(top_percentile: Option<f64>, score_threshold_percentile: Option<f64>) -> Option<HttpResponse> {
+    let message = if top_percentile.is_some_and(|p| p.is_nan() || p <= 0.0 || p > 1.0) {
+        "top_percentile must be greater than 0 and at most 1"
+    } else if score_threshold_percentile.is_some_and(|p| !(0.0..=1.0).contains(&p)) {
+        "score_threshold_percentile must be between 0 and 1"
+    } else {
+        return None;
+    };
+    Some(HttpResponse::BadRequest().json(ErrorResponse {
+        error: "Invalid percentile".to_string(),
+        message: message.to_string(),
+    }))
+}
+
+fn check_max_distance(max_distance_km: Option<f64>) -> Option<HttpResponse> {
+    max_distance_km.filter(|radius_km| radius_km.is_nan() || *radius_km <= 0.0).map(|_| {
+        HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Invalid distance".to_string(),
+            message: "max_distance_km must be positive".to_string(),
+        })
+    })
+}
+
+fn localize_response(service: &RecommendationService, response: &mut RecommendationResponse, currency: Option<&str>) {
+    if let Some(currency) = currency {
+        for recommendation in &mut response.recommendations {
+            service.currency().localize(recommendation, currency);
+        }
+    }
+}
 
 pub async fn get_property_recommendations(
     path: web::Path<i32>,
@@ -9,16 +56,23 @@ pub async fn get_property_recommendations(
 ) -> Result<HttpResponse> {
     let property_id = path.into_inner();
     
-    // Validate weights if provided
-    if let Err(e) = query.validate_weights() {
-        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+    // Merge any weight overrides with the configured defaults
+    let weights = match query.resolve_weights(service.default_weights()) {
+        Ok(weights) => weights,
+        Err(e) => return Ok(HttpResponse::BadRequest().json(ErrorResponse {
             error: "Invalid weights".to_string(),
             message: e,
-        }));
-    }
-    
-    let (budget_weight, location_weight, property_type_weight, size_weight) = query.get_weights();
+        })),
+    };
     
+    if let Some(response) = check_percentiles(query.top_percentile, query.score_threshold_percentile) {
+        return Ok(response);
+    }
+
+    if let Some(response) = check_currency(&service, query.currency.as_deref()) {
+        return Ok(response);
+    }
+
     match service.get_recommendations_for_property(
         property_id, 
         query.limit, 
@@ -26,12 +80,12 @@ pub async fn get_property_recommendations(
         query.top_k,
         query.top_percentile,
         query.score_threshold_percentile,
-        budget_weight,
-        location_weight,
-        property_type_weight,
-        size_weight,
+        weights,
     ).await {
-        Ok(recommendations) => Ok(HttpResponse::Ok().json(recommendations)),
+        Ok(mut recommendations) => {
+            localize_response(&service, &mut recommendations, query.currency.as_deref());
+            Ok(HttpResponse::Ok().json(recommendations))
+        },
         Err(e) => Ok(HttpResponse::InternalServerError().json(ErrorResponse {
             error: "Failed to get recommendations".to_string(),
             message: e.to_string(),
@@ -46,16 +100,27 @@ pub async fn get_contact_recommendations(
 ) -> Result<HttpResponse> {
     let contact_id = path.into_inner();
     
-    // Validate weights if provided
-    if let Err(e) = query.validate_weights() {
-        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+    // Merge any weight overrides with the configured defaults
+    let weights = match query.resolve_weights(service.default_weights()) {
+        Ok(weights) => weights,
+        Err(e) => return Ok(HttpResponse::BadRequest().json(ErrorResponse {
             error: "Invalid weights".to_string(),
             message: e,
-        }));
-    }
-    
-    let (budget_weight, location_weight, property_type_weight, size_weight) = query.get_weights();
+        })),
+    };
     
+    if let Some(response) = check_max_distance(query.max_distance_km) {
+        return Ok(response);
+    }
+
+    if let Some(response) = check_percentiles(query.top_percentile, query.score_threshold_percentile) {
+        return Ok(response);
+    }
+
+    if let Some(response) = check_currency(&service, query.currency.as_deref()) {
+        return Ok(response);
+    }
+
     match service.get_recommendations_for_contact(
         contact_id, 
         query.limit, 
@@ -63,12 +128,16 @@ pub async fn get_contact_recommendations(
         query.top_k,
         query.top_percentile,
         query.score_threshold_percentile,
-        budget_weight,
-        location_weight,
-        property_type_weight,
-        size_weight,
+        query.max_distance_km,
+        query.excluded_property_types(),
+        query.include_unavailable,
+        query.sort_by,
+        weights,
     ).await {
-        Ok(recommendations) => Ok(HttpResponse::Ok().json(recommendations)),
+        Ok(mut recommendations) => {
+            localize_response(&service, &mut recommendations, query.currency.as_deref());
+            Ok(HttpResponse::Ok().json(recommendations))
+        },
         Err(e) => Ok(HttpResponse::InternalServerError().json(ErrorResponse {
             error: "Failed to get recommendations".to_string(),
             message: e.to_string(),
@@ -83,13 +152,17 @@ pub async fn get_bulk_recommendations(
     let req = request.into_inner();
     
     // Validate weights if provided
-    if let Err(e) = req.validate_weights() {
+    if let Err(e) = req.resolve_weights(service.default_weights()) {
         return Ok(HttpResponse::BadRequest().json(ErrorResponse {
             error: "Invalid weights".to_string(),
             message: e,
         }));
     }
     
+    if let Some(response) = check_percentiles(req.top_percentile, req.score_threshold_percentile) {
+        return Ok(response);
+    }
+
     match service.get_bulk_recommendations(req).await {
         Ok(response) => Ok(HttpResponse::Ok().json(response)),
         Err(e) => Ok(HttpResponse::InternalServerError().json(ErrorResponse {
@@ -99,6 +172,157 @@ pub async fn get_bulk_recommendations(
     }
 }
 
+pub async fn get_batch_contact_recommendations(
+    request: web::Json<BatchContactRecommendationRequest>,
+    service: web::Data<RecommendationService>,
+) -> Result<HttpResponse> {
+    let req = request.into_inner();
+    
+    // Validate weights if provided
+    if let Err(e) = req.resolve_weights(service.default_weights()) {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Invalid weights".to_string(),
+            message: e,
+        }));
+    }
+
+    if req.contact_ids.is_empty() || req.contact_ids.len() > MAX_CONTACTS_PER_BATCH {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Invalid contact ids".to_string(),
+            message: format!("Provide between 1 and {} contact ids", MAX_CONTACTS_PER_BATCH),
+        }));
+    }
+    
+    if let Some(response) = check_max_distance(req.max_distance_km) {
+        return Ok(response);
+    }
+
+    if let Some(response) = check_percentiles(req.top_percentile, req.score_threshold_percentile) {
+        return Ok(response);
+    }
+
+    match service.get_recommendations_for_contacts(&req.contact_ids, &req).await {
+        Ok(response) => Ok(HttpResponse::Ok().json(response)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(ErrorResponse {
+            error: "Failed to get batch recommendations".to_string(),
+            message: e.to_string(),
+        })),
+    }
+}
+
+pub async fn stream_contact_recommendations(
+    path: web::Path<i32>,
+    query: web::Query<RecommendationQuery>,
+    service: web::Data<RecommendationService>,
+) -> Result<HttpResponse> {
+    let contact_id = path.into_inner();
+
+    let weights = match query.resolve_weights(service.default_weights()) {
+        Ok(weights) => weights,
+        Err(e) => return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Invalid weights".to_string(),
+            message: e,
+        })),
+    };
+
+    if let Some(response) = check_max_distance(query.max_distance_km) {
+        return Ok(response);
+    }
+
+    if let Some(response) = check_currency(&service, query.currency.as_deref()) {
+        return Ok(response);
+    }
+
+    let stream = match service.stream_recommendations_for_contact(
+        contact_id,
+        query.min_score,
+        query.max_distance_km,
+        query.excluded_property_types(),
+        query.include_unavailable,
+        weights,
+    ).await {
+        Ok(Some(stream)) => stream,
+        Ok(None) => return Ok(HttpResponse::NotFound().json(ErrorResponse {
+            error: "Not found".to_string(),
+            message: format!("Contact {} does not exist", contact_id),
+        })),
+        Err(e) => return Ok(HttpResponse::InternalServerError().json(ErrorResponse {
+            error: "Failed to stream recommendations".to_string(),
+            message: e.to_string(),
+        })),
+    };
+
+    // One JSON document per line; a failure mid-stream is reported as an error line
+    let currency = query.into_inner().currency;
+    let body = stream.map(move |item| {
+        let line = match item {
+            Ok(mut recommendation) => {
+                if let Some(currency) = &currency {
+                    service.currency().localize(&mut recommendation, currency);
+                }
+                serde_json::to_vec(&recommendation)
+            }
+            Err(e) => serde_json::to_vec(&ErrorResponse {
+                error: "Failed to score property".to_string(),
+                message: e.to_string(),
+            }),
+        };
+
+        line.map(|mut bytes| {
+            bytes.push(b'\n');
+            web::Bytes::from(bytes)
+        })
+        .map_err(actix_web::error::ErrorInternalServerError)
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .streaming(body))
+}
+
+#[derive(serde::Deserialize)]
+pub struct ExplainQuery {
+    pub contact_id: i32,
+    pub property_id: i32,
+    pub budget_weight: Option<f64>,
+    pub location_weight: Option<f64>,
+    pub property_type_weight: Option<f64>,
+    pub size_weight: Option<f64>,
+}
+
+pub async fn explain_recommendation(
+    query: web::Query<ExplainQuery>,
+    service: web::Data<RecommendationService>,
+) -> Result<HttpResponse> {
+    let weights = match service.default_weights().with_overrides(
+        query.budget_weight,
+        query.location_weight,
+        query.property_type_weight,
+        query.size_weight,
+    ) {
+        Ok(weights) => weights,
+        Err(e) => return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Invalid weights".to_string(),
+            message: e,
+        })),
+    };
+
+    match service.explain(query.contact_id, query.property_id, weights).await {
+        Ok(Some(explanation)) => Ok(HttpResponse::Ok().json(explanation)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(ErrorResponse {
+            error: "Not found".to_string(),
+            message: format!(
+                "Contact {} or property {} does not exist",
+                query.contact_id, query.property_id
+            ),
+        })),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(ErrorResponse {
+            error: "Failed to explain recommendation".to_string(),
+            message: e.to_string(),
+        })),
+    }
+}
+
 #[derive(serde::Deserialize)]
 pub struct RecommendationQuery {
     pub limit: Option<usize>,
@@ -106,6 +330,14 @@ pub struct RecommendationQuery {
     pub top_k: Option<usize>,
     pub top_percentile: Option<f64>, // Top X% of scores (e.g., 0.1 for top 10%)
     pub score_threshold_percentile: Option<f64>, // Only return scores above Xth percentile
+    pub max_distance_km: Option<f64>, // Only consider properties within this radius of a preferred location
+    pub currency: Option<String>, // Return prices and budgets in this currency
+    #[serde(alias = "exclude_property_types")]
+    pub excluded_property_types: Option<String>, // Comma-separated; replaces the contact's stored type exclusions
+    #[serde(default)]
+    pub include_unavailable: bool, // Also return sold, rented and under-offer listings, e.g. for analytics
+    #[serde(default)]
+    pub sort_by: RecommendationSort,
     pub budget_weight: Option<f64>,
     pub location_weight: Option<f64>,
     pub property_type_weight: Option<f64>,
@@ -113,31 +345,24 @@ pub struct RecommendationQuery {
 }
 
 impl RecommendationQuery {
-    pub fn get_weights(&self) -> (f64, f64, f64, f64) {
-        let budget = self.budget_weight.unwrap_or(0.3);
-        let location = self.location_weight.unwrap_or(0.25);
-        let property_type = self.property_type_weight.unwrap_or(0.2);
-        let size = self.size_weight.unwrap_or(0.25);
-        
-        (budget, location, property_type, size)
+    /// Splits the comma-separated `excluded_property_types` parameter.
+    pub fn excluded_property_types(&self) -> Option<Vec<String>> {
+        self.excluded_property_types.as_deref().map(|types| {
+            types.split(',')
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
     }
-    
-    pub fn validate_weights(&self) -> Result<(), String> {
-        if let (Some(b), Some(l), Some(p), Some(s)) = (
+
+    pub fn resolve_weights(&self, defaults: ScoringWeights) -> Result<ScoringWeights, String> {
+        defaults.with_overrides(
             self.budget_weight,
             self.location_weight,
             self.property_type_weight,
             self.size_weight,
-        ) {
-            let sum = b + l + p + s;
-            if (sum - 1.0).abs() > 0.001 {
-                return Err(format!("Weights must sum to 1.0, got {:.3}", sum));
-            }
-            if b < 0.0 || l < 0.0 || p < 0.0 || s < 0.0 {
-                return Err("All weights must be non-negative".to_string());
-            }
-        }
-        Ok(())
+        )
     }
 }
 
@@ -152,6 +377,33 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
         web::scope("/recommendations")
             .route("/property/{property_id}", web::get().to(get_property_recommendations))
             .route("/contact/{contact_id}", web::get().to(get_contact_recommendations))
+            .route("/contact/{contact_id}/stream", web::get().to(stream_contact_recommendations))
             .route("/bulk", web::post().to(get_bulk_recommendations))
+            .route("/batch", web::post().to(get_batch_contact_recommendations))
+            .route("/explain", web::get().to(explain_recommendation))
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn excluded_property_types_accepts_both_spellings() {
+        for query in ["excluded_property_types=villa,%20studio,", "exclude_property_types=villa,%20studio,"] {
+            let query = web::Query::<RecommendationQuery>::from_query(query).unwrap();
+            assert_eq!(
+                query.excluded_property_types(),
+                Some(vec!["villa".to_string(), "studio".to_string()])
+            );
+        }
+    }
+
+    #[test]
+    fn max_distance_must_be_positive() {
+        assert!(check_max_distance(None).is_none());
+        assert!(check_max_distance(Some(5.0)).is_none());
+        assert!(check_max_distance(Some(0.0)).is_some());
+        assert!(check_max_distance(Some(f64::NAN)).is_some());
+    }
+}
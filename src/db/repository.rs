@@ -1,161 +1,355 @@
-use crate::models::{Contact, Property, Location, NamedLocation};
+use crate::config::RetryConfig;
+use crate::models::{normalize_phone, Contact, UpdateContactRequest, Property, PropertyAvailability, PropertyFilter, PropertySort, Location, NamedLocation};
 use anyhow::Result;
-use sqlx::{PgPool, Row};
+use futures_util::stream::BoxStream;
+use sqlx::{postgres::PgRow, PgPool, Postgres, QueryBuilder, Row};
+use std::future::Future;
+use std::time::Duration;
+
+const PROPERTY_COLUMNS: &str =
+    "id, address, lat, lon, price, area_sqm, property_type, number_of_rooms, amenities, listed_at, currency, availability, created_at, updated_at, deleted_at";
+
+/// Rows fetched per round trip by `stream_all_properties`.
+const STREAM_CHUNK_SIZE: i64 = 500;
+
+const CONTACT_COLUMNS: &str =
+    "id, name, preferred_locations, min_budget, max_budget, min_area_sqm, max_area_sqm, property_types, min_rooms, max_rooms, required_amenities, email, phone, currency, excluded_locations, excluded_property_types, created_at, updated_at, deleted_at";
+
+fn property_from_row(row: &PgRow) -> Result<Property> {
+    let amenities: Vec<String> = serde_json::from_value(row.get::<serde_json::Value, _>("amenities"))?;
+
+    Ok(Property {
+        id: row.get("id"),
+        address: row.get("address"),
+        location: Location {
+            lat: row.get("lat"),
+            lon: row.get("lon"),
+        },
+        price: row.get("price"),
+        area_sqm: row.get("area_sqm"),
+        property_type: row.get("property_type"),
+        number_of_rooms: row.get("number_of_rooms"),
+        amenities,
+        listed_at: row.get("listed_at"),
+        currency: row.get("currency"),
+        availability: row.get::<String, _>("availability").parse().map_err(anyhow::Error::msg)?,
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        deleted_at: row.get("deleted_at"),
+    })
+}
+
+fn contact_from_row(row: &PgRow) -> Result<Contact> {
+    let preferred_locations: Vec<NamedLocation> = serde_json::from_value(row.get::<serde_json::Value, _>("preferred_locations"))?;
+    let property_types: Vec<String> = serde_json::from_value(row.get::<serde_json::Value, _>("property_types"))?;
+    let required_amenities: Vec<String> = serde_json::from_value(row.get::<serde_json::Value, _>("required_amenities"))?;
+    let excluded_locations: Vec<NamedLocation> = serde_json::from_value(row.get::<serde_json::Value, _>("excluded_locations"))?;
+    let excluded_property_types: Vec<String> = serde_json::from_value(row.get::<serde_json::Value, _>("excluded_property_types"))?;
+
+    Ok(Contact {
+        id: row.get("id"),
+        name: row.get("name"),
+        preferred_locations,
+        min_budget: row.get("min_budget"),
+        max_budget: row.get("max_budget"),
+        min_area_sqm: row.get("min_area_sqm"),
+        max_area_sqm: row.get("max_area_sqm"),
+        property_types,
+        min_rooms: row.get("min_rooms"),
+        max_rooms: row.get("max_rooms"),
+        required_amenities,
+        email: row.get("email"),
+        phone: row.get("phone"),
+        currency: row.get("currency"),
+        excluded_locations,
+        excluded_property_types,
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        deleted_at: row.get("deleted_at"),
+    })
+}
+
+/// Whether an error is likely to go away on its own: dropped connections,
+/// deadlocks, serialization failures and a server restarting. Constraint
+/// violations and bad queries are permanent and fail immediately.
+fn is_transient(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Io(_) => true,
+        sqlx::Error::Database(db_error) => db_error.code().is_some_and(|code| {
+            // 08: connection exception, 40001: serialization failure, 40P01: deadlock,
+            // 57P01-57P03: admin/crash shutdown or cannot connect now
+            code.starts_with("08")
+                || matches!(code.as_ref(), "40001" | "40P01" | "57P01" | "57P02" | "57P03")
+        }),
+        _ => false,
+    }
+}
+
+/// Escapes LIKE wildcards so user input matches literally. Backslash is
+/// Postgres' default LIKE escape character.
+fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Appends the WHERE clause for a property search. Shared by the page and
+/// count queries so both always agree.
+fn push_property_filters(builder: &mut QueryBuilder<'_, Postgres>, filter: &PropertyFilter) {
+    if filter.include_deleted {
+        builder.push(" WHERE TRUE");
+    } else {
+        builder.push(" WHERE deleted_at IS NULL");
+    }
+    if let Some(property_type) = &filter.property_type {
+        builder.push(" AND LOWER(property_type) = LOWER(").push_bind(property_type.clone()).push(")");
+    }
+    if let Some(min_price) = filter.min_price {
+        builder.push(" AND price >= ").push_bind(min_price);
+    }
+    if let Some(max_price) = filter.max_price {
+        builder.push(" AND price <= ").push_bind(max_price);
+    }
+    if let Some(min_area) = filter.min_area {
+        builder.push(" AND area_sqm >= ").push_bind(min_area);
+    }
+    if let Some(max_area) = filter.max_area {
+        builder.push(" AND area_sqm <= ").push_bind(max_area);
+    }
+    if let Some(min_rooms) = filter.min_rooms {
+        builder.push(" AND number_of_rooms >= ").push_bind(min_rooms);
+    }
+    if let Some(max_rooms) = filter.max_rooms {
+        builder.push(" AND number_of_rooms <= ").push_bind(max_rooms);
+    }
+    if let Some(address) = &filter.address {
+        builder.push(" AND address ILIKE ").push_bind(format!("%{}%", escape_like(address.trim())));
+    }
+    if let Some(availability) = filter.availability {
+        builder.push(" AND availability = ").push_bind(availability.as_str());
+    }
+    if let Some((lat, lon, radius_km)) = filter.location() {
+        // Same earth_box prefilter as find_properties_near, served by the GiST index
+        builder.push(" AND earth_box(ll_to_earth(").push_bind(lat).push(", ").push_bind(lon)
+            .push("), ").push_bind(radius_km * 1000.0).push(") @> ll_to_earth(lat, lon)");
+        builder.push(" AND earth_distance(ll_to_earth(").push_bind(lat).push(", ").push_bind(lon)
+            .push("), ll_to_earth(lat, lon)) <= ").push_bind(radius_km * 1000.0);
+    }
+}
 
 #[derive(Clone)]
 pub struct Repository {
     pool: PgPool,
+    retry: RetryConfig,
 }
 
 impl Repository {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(pool: PgPool, retry: RetryConfig) -> Self {
+        Self { pool, retry }
+    }
+
+    /// Runs an idempotent read, retrying transient failures with exponential
+    /// backoff. Writes must not go through here since they may have applied.
+    async fn read_with_retry<T, F, Fut>(&self, operation: &str, mut query: F) -> Result<T, sqlx::Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, sqlx::Error>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match query().await {
+                Err(e) if attempt < self.retry.max_retries && is_transient(&e) => {
+                    let delay = Duration::from_millis(self.retry.base_delay_ms.saturating_mul(1 << attempt.min(16)));
+                    attempt += 1;
+                    log::warn!(
+                        "Transient database error in {} (retry {}/{} in {}ms): {}",
+                        operation, attempt, self.retry.max_retries, delay.as_millis(), e
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    // Health operations
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Open connections and how many of them are idle.
+    pub fn pool_stats(&self) -> (u32, usize) {
+        (self.pool.size(), self.pool.num_idle())
+    }
+
+    pub async fn close(&self) {
+        self.pool.close().await;
     }
 
     // Property operations
     pub async fn get_property_by_id(&self, id: i32) -> Result<Option<Property>> {
-        let row = sqlx::query(
-            "SELECT id, address, lat, lon, price, area_sqm, property_type, number_of_rooms FROM properties WHERE id = $1"
-        )
-        .bind(id)
-        .fetch_optional(&self.pool)
-        .await?;
+        let sql = format!("SELECT {} FROM properties WHERE id = $1 AND deleted_at IS NULL", PROPERTY_COLUMNS);
+        let row = self.read_with_retry("get_property_by_id", || {
+            sqlx::query(&sql).bind(id).fetch_optional(&self.pool)
+        }).await?;
 
-        if let Some(row) = row {
-            Ok(Some(Property {
-                id: row.get("id"),
-                address: row.get("address"),
-                location: Location {
-                    lat: row.get("lat"),
-                    lon: row.get("lon"),
-                },
-                price: row.get("price"),
-                area_sqm: row.get("area_sqm"),
-                property_type: row.get("property_type"),
-                number_of_rooms: row.get("number_of_rooms"),
-            }))
-        } else {
-            Ok(None)
-        }
+        row.as_ref().map(property_from_row).transpose()
     }
 
     pub async fn get_all_active_properties(&self) -> Result<Vec<Property>> {
-        let rows = sqlx::query(
-            "SELECT id, address, lat, lon, price, area_sqm, property_type, number_of_rooms FROM properties"
-        )
-        .fetch_all(&self.pool)
-        .await?;
+        let sql = format!("SELECT {} FROM properties WHERE deleted_at IS NULL", PROPERTY_COLUMNS);
+        let rows = self.read_with_retry("get_all_active_properties", || {
+            sqlx::query(&sql).fetch_all(&self.pool)
+        }).await?;
 
-        let mut properties = Vec::new();
-        for row in rows {
-            properties.push(Property {
-                id: row.get("id"),
-                address: row.get("address"),
-                location: Location {
-                    lat: row.get("lat"),
-                    lon: row.get("lon"),
-                },
-                price: row.get("price"),
-                area_sqm: row.get("area_sqm"),
-                property_type: row.get("property_type"),
-                number_of_rooms: row.get("number_of_rooms"),
-            });
-        }
+        rows.iter().map(property_from_row).collect()
+    }
+
+    /// Streams every property in id order without loading the table into
+    /// memory. Rows are read in keyset-paginated chunks, so a pooled
+    /// connection is only held while a chunk is fetched, not for the life of
+    /// a slow consumer.
+    pub fn stream_all_properties(&self) -> BoxStream<'static, Result<Property>> {
+        let repository = self.clone();
 
-        Ok(properties)
+        Box::pin(async_stream::try_stream! {
+            let sql = format!(
+                "SELECT {} FROM properties WHERE deleted_at IS NULL AND id > $1 ORDER BY id LIMIT $2",
+                PROPERTY_COLUMNS
+            );
+            let mut last_id = i32::MIN;
+            loop {
+                let rows = repository.read_with_retry("stream_all_properties", || {
+                    sqlx::query(&sql)
+                        .bind(last_id)
+                        .bind(STREAM_CHUNK_SIZE)
+                        .fetch_all(&repository.pool)
+                }).await?;
+                let exhausted = (rows.len() as i64) < STREAM_CHUNK_SIZE;
+
+                for row in &rows {
+                    let property = property_from_row(row)?;
+                    last_id = property.id;
+                    yield property;
+                }
+                if exhausted {
+                    break;
+                }
+            }
+        })
     }
 
     pub async fn get_properties_by_ids(&self, ids: &[i32]) -> Result<Vec<Property>> {
-        let rows = sqlx::query(
-            "SELECT id, address, lat, lon, price, area_sqm, property_type, number_of_rooms FROM properties WHERE id = ANY($1)"
-        )
-        .bind(ids)
-        .fetch_all(&self.pool)
-        .await?;
+        let sql = format!("SELECT {} FROM properties WHERE id = ANY($1) AND deleted_at IS NULL", PROPERTY_COLUMNS);
+        let rows = self.read_with_retry("get_properties_by_ids", || {
+            sqlx::query(&sql).bind(ids).fetch_all(&self.pool)
+        }).await?;
 
-        let mut properties = Vec::new();
-        for row in rows {
-            properties.push(Property {
-                id: row.get("id"),
-                address: row.get("address"),
-                location: Location {
-                    lat: row.get("lat"),
-                    lon: row.get("lon"),
-                },
-                price: row.get("price"),
-                area_sqm: row.get("area_sqm"),
-                property_type: row.get("property_type"),
-                number_of_rooms: row.get("number_of_rooms"),
-            });
-        }
+        rows.iter().map(property_from_row).collect()
+    }
 
-        Ok(properties)
+    /// Returns properties within `radius_km` of a point, nearest first.
+    /// The `earth_box` prefilter is served by the GiST index from migration 004.
+    pub async fn find_properties_near(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+        limit: Option<i64>,
+    ) -> Result<Vec<Property>> {
+        let sql = format!(
+            "SELECT {} FROM properties
+             WHERE deleted_at IS NULL
+               AND earth_box(ll_to_earth($1, $2), $3) @> ll_to_earth(lat, lon)
+               AND earth_distance(ll_to_earth($1, $2), ll_to_earth(lat, lon)) <= $3
+             ORDER BY earth_distance(ll_to_earth($1, $2), ll_to_earth(lat, lon))
+             LIMIT $4",
+            PROPERTY_COLUMNS
+        );
+        let rows = self.read_with_retry("find_properties_near", || {
+            sqlx::query(&sql)
+                .bind(lat)
+                .bind(lon)
+                .bind(radius_km * 1000.0)
+                .bind(limit)
+                .fetch_all(&self.pool)
+        }).await?;
+
+        rows.iter().map(property_from_row).collect()
+    }
+
+    /// Returns one page of properties matching the filter, plus the total
+    /// number of matches for pagination. All predicates run in SQL.
+    pub async fn search_properties(&self, filter: &PropertyFilter) -> Result<(Vec<Property>, i64)> {
+        let total: i64 = self.read_with_retry("search_properties count", || async {
+            let mut query = QueryBuilder::new("SELECT COUNT(*) AS total FROM properties");
+            push_property_filters(&mut query, filter);
+            query.build().fetch_one(&self.pool).await
+        }).await?.get("total");
+
+        let rows = self.read_with_retry("search_properties", || async {
+            let mut query = QueryBuilder::new(format!("SELECT {} FROM properties", PROPERTY_COLUMNS));
+            push_property_filters(&mut query, filter);
+            match (filter.sort_by, filter.location()) {
+                (PropertySort::Distance, Some((lat, lon, _))) => {
+                    query.push(" ORDER BY earth_distance(ll_to_earth(").push_bind(lat).push(", ")
+                        .push_bind(lon).push("), ll_to_earth(lat, lon))");
+                }
+                (PropertySort::PriceAsc, _) => { query.push(" ORDER BY price ASC"); }
+                (PropertySort::PriceDesc, _) => { query.push(" ORDER BY price DESC"); }
+                (PropertySort::AreaAsc, _) => { query.push(" ORDER BY area_sqm ASC"); }
+                (PropertySort::AreaDesc, _) => { query.push(" ORDER BY area_sqm DESC"); }
+                _ => { query.push(" ORDER BY listed_at DESC"); }
+            }
+            // Tie-break on id so pages never overlap
+            query.push(", id ASC LIMIT ").push_bind(filter.page_size())
+                // validate() rejects overflowing pages; past-the-end is the safe reading anyway
+                .push(" OFFSET ").push_bind(filter.offset().unwrap_or(i64::MAX));
+            query.build().fetch_all(&self.pool).await
+        }).await?;
+
+        let properties = rows.iter().map(property_from_row).collect::<Result<Vec<_>>>()?;
+        Ok((properties, total))
     }
 
     // Contact operations
     pub async fn get_contact_by_id(&self, id: i32) -> Result<Option<Contact>> {
-        let row = sqlx::query(
-            "SELECT id, name, preferred_locations, min_budget, max_budget, min_area_sqm, max_area_sqm, property_types, min_rooms FROM contacts WHERE id = $1"
-        )
-        .bind(id)
-        .fetch_optional(&self.pool)
-        .await?;
+        let sql = format!("SELECT {} FROM contacts WHERE id = $1 AND deleted_at IS NULL", CONTACT_COLUMNS);
+        let row = self.read_with_retry("get_contact_by_id", || {
+            sqlx::query(&sql).bind(id).fetch_optional(&self.pool)
+        }).await?;
 
-        if let Some(row) = row {
-            let preferred_locations: Vec<NamedLocation> = serde_json::from_value(row.get::<serde_json::Value, _>("preferred_locations"))?;
-            let property_types: Vec<String> = serde_json::from_value(row.get::<serde_json::Value, _>("property_types"))?;
-            
-            Ok(Some(Contact {
-                id: row.get("id"),
-                name: row.get("name"),
-                preferred_locations,
-                min_budget: row.get("min_budget"),
-                max_budget: row.get("max_budget"),
-                min_area_sqm: row.get("min_area_sqm"),
-                max_area_sqm: row.get("max_area_sqm"),
-                property_types,
-                min_rooms: row.get("min_rooms"),
-            }))
-        } else {
-            Ok(None)
-        }
+        row.as_ref().map(contact_from_row).transpose()
     }
 
     pub async fn get_all_active_contacts(&self) -> Result<Vec<Contact>> {
-        let rows = sqlx::query(
-            "SELECT id, name, preferred_locations, min_budget, max_budget, min_area_sqm, max_area_sqm, property_types, min_rooms FROM contacts"
-        )
-        .fetch_all(&self.pool)
-        .await?;
-
-        let mut contacts = Vec::new();
-        for row in rows {
-            let preferred_locations: Vec<NamedLocation> = serde_json::from_value(row.get::<serde_json::Value, _>("preferred_locations"))?;
-            let property_types: Vec<String> = serde_json::from_value(row.get::<serde_json::Value, _>("property_types"))?;
-            
-            contacts.push(Contact {
-                id: row.get("id"),
-                name: row.get("name"),
-                preferred_locations,
-                min_budget: row.get("min_budget"),
-                max_budget: row.get("max_budget"),
-                min_area_sqm: row.get("min_area_sqm"),
-                max_area_sqm: row.get("max_area_sqm"),
-                property_types,
-                min_rooms: row.get("min_rooms"),
-            });
-        }
+        let sql = format!("SELECT {} FROM contacts WHERE deleted_at IS NULL", CONTACT_COLUMNS);
+        let rows = self.read_with_retry("get_all_active_contacts", || {
+            sqlx::query(&sql).fetch_all(&self.pool)
+        }).await?;
 
-        Ok(contacts)
+        rows.iter().map(contact_from_row).collect()
     }
 
+    /// Validates and inserts a contact. Validation failures surface as a
+    /// `ContactValidationError` so callers can report every bad field.
     pub async fn create_contact(&self, contact: &Contact) -> Result<Contact> {
+        contact.validate()?;
+
+        // Store trimmed email and separator-free phone so lookups stay consistent
+        let email = contact.email.as_deref().map(str::trim);
+        let phone = contact.phone.as_deref().map(normalize_phone);
+
         let preferred_locations_json = serde_json::to_value(&contact.preferred_locations)?;
         let property_types_json = serde_json::to_value(&contact.property_types)?;
+        let required_amenities_json = serde_json::to_value(&contact.required_amenities)?;
+        let excluded_locations_json = serde_json::to_value(&contact.excluded_locations)?;
+        let excluded_property_types_json = serde_json::to_value(&contact.excluded_property_types)?;
 
         let row = sqlx::query(
-            "INSERT INTO contacts (name, preferred_locations, min_budget, max_budget, min_area_sqm, max_area_sqm, property_types, min_rooms) 
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id"
+            "INSERT INTO contacts (name, preferred_locations, min_budget, max_budget, min_area_sqm, max_area_sqm, property_types, min_rooms, required_amenities, email, phone, currency, excluded_locations, excluded_property_types, max_rooms) 
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15) RETURNING id, created_at, updated_at"
         )
         .bind(&contact.name)
         .bind(preferred_locations_json)
@@ -165,19 +359,136 @@ impl Repository {
         .bind(contact.max_area_sqm)
         .bind(property_types_json)
         .bind(contact.min_rooms)
+        .bind(required_amenities_json)
+        .bind(email)
+        .bind(&phone)
+        .bind(&contact.currency)
+        .bind(excluded_locations_json)
+        .bind(excluded_property_types_json)
+        .bind(contact.max_rooms)
         .fetch_one(&self.pool)
         .await?;
 
         let id: i32 = row.get("id");
         let mut new_contact = contact.clone();
         new_contact.id = id;
+        new_contact.email = email.map(str::to_string);
+        new_contact.phone = phone;
+        new_contact.created_at = row.get("created_at");
+        new_contact.updated_at = row.get("updated_at");
+        new_contact.deleted_at = None;
         Ok(new_contact)
     }
 
+    /// Applies a partial update to a contact and validates the result before
+    /// saving, so a change can never leave an invalid contact behind. Returns
+    /// `None` if the contact does not exist.
+    pub async fn update_contact(&self, id: i32, update: UpdateContactRequest) -> Result<Option<Contact>> {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM contacts WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+            CONTACT_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let mut contact = match row {
+            Some(row) => contact_from_row(&row)?,
+            None => return Ok(None),
+        };
+
+        update.apply_to(&mut contact);
+        contact.validate()?;
+        contact.email = contact.email.as_deref().map(|email| email.trim().to_string());
+        contact.phone = contact.phone.as_deref().map(normalize_phone);
+
+        let row = sqlx::query(
+            "UPDATE contacts SET name = $2, preferred_locations = $3, min_budget = $4, max_budget = $5,
+                 min_area_sqm = $6, max_area_sqm = $7, property_types = $8, min_rooms = $9,
+                 required_amenities = $10, email = $11, phone = $12, currency = $13,
+                 excluded_locations = $14, excluded_property_types = $15, max_rooms = $16, updated_at = NOW()
+             WHERE id = $1 RETURNING updated_at"
+        )
+        .bind(id)
+        .bind(&contact.name)
+        .bind(serde_json::to_value(&contact.preferred_locations)?)
+        .bind(contact.min_budget)
+        .bind(contact.max_budget)
+        .bind(contact.min_area_sqm)
+        .bind(contact.max_area_sqm)
+        .bind(serde_json::to_value(&contact.property_types)?)
+        .bind(contact.min_rooms)
+        .bind(serde_json::to_value(&contact.required_amenities)?)
+        .bind(&contact.email)
+        .bind(&contact.phone)
+        .bind(&contact.currency)
+        .bind(serde_json::to_value(&contact.excluded_locations)?)
+        .bind(serde_json::to_value(&contact.excluded_property_types)?)
+        .bind(contact.max_rooms)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        contact.updated_at = row.get("updated_at");
+        Ok(Some(contact))
+    }
+
+    /// Marks a contact as deleted without removing the row. Returns `false` if
+    /// it does not exist or was already deleted.
+    pub async fn soft_delete_contact(&self, id: i32) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE contacts SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL"
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Sets a property's availability, returning the previous status, or
+    /// `None` if the property does not exist.
+    pub async fn update_property_availability(
+        &self,
+        id: i32,
+        availability: PropertyAvailability,
+    ) -> Result<Option<PropertyAvailability>> {
+        let row = sqlx::query(
+            "UPDATE properties SET availability = $2, updated_at = NOW()
+             FROM (SELECT id, availability AS previous FROM properties WHERE id = $1 AND deleted_at IS NULL FOR UPDATE) old
+             WHERE properties.id = old.id
+             RETURNING old.previous"
+        )
+        .bind(id)
+        .bind(availability.as_str())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| row.get::<String, _>("previous").parse().map_err(anyhow::Error::msg))
+            .transpose()
+    }
+
+    /// Marks a property as deleted without removing the row, so past
+    /// recommendations keep a valid reference. Returns `false` if it does not
+    /// exist or was already deleted.
+    pub async fn soft_delete_property(&self, id: i32) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE properties SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL"
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
     pub async fn create_property(&self, property: &Property) -> Result<Property> {
+        let amenities_json = serde_json::to_value(&property.amenities)?;
+
         let row = sqlx::query(
-            "INSERT INTO properties (address, lat, lon, price, area_sqm, property_type, number_of_rooms) 
-             VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id"
+            "INSERT INTO properties (address, lat, lon, price, area_sqm, property_type, number_of_rooms, amenities, listed_at, currency, availability) 
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) RETURNING id, created_at, updated_at"
         )
         .bind(&property.address)
         .bind(property.location.lat)
@@ -186,12 +497,19 @@ impl Repository {
         .bind(property.area_sqm)
         .bind(&property.property_type)
         .bind(property.number_of_rooms)
+        .bind(amenities_json)
+        .bind(property.listed_at)
+        .bind(&property.currency)
+        .bind(property.availability.as_str())
         .fetch_one(&self.pool)
         .await?;
 
         let id: i32 = row.get("id");
         let mut new_property = property.clone();
         new_property.id = id;
+        new_property.created_at = row.get("created_at");
+        new_property.updated_at = row.get("updated_at");
+        new_property.deleted_at = None;
         Ok(new_property)
     }
 
@@ -206,3 +524,31 @@ impl Repository {
         r * c
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter_sql(query: &str) -> String {
+        let filter = actix_web::web::Query::<PropertyFilter>::from_query(query).unwrap().into_inner();
+        let mut builder = QueryBuilder::new("SELECT id FROM properties");
+        push_property_filters(&mut builder, &filter);
+        builder.sql().to_string()
+    }
+
+    #[test]
+    fn soft_deleted_properties_are_hidden_unless_requested() {
+        assert!(filter_sql("").contains("deleted_at IS NULL"));
+        assert!(filter_sql("include_deleted=false").contains("deleted_at IS NULL"));
+
+        let sql = filter_sql("include_deleted=true&min_price=10");
+        assert!(!sql.contains("deleted_at"));
+        assert!(sql.contains("WHERE TRUE AND price >= $1"));
+    }
+
+    #[test]
+    fn address_wildcards_match_literally() {
+        assert_eq!(escape_like("100%_pure\\"), "100\\%\\_pure\\\\");
+        assert_eq!(escape_like("12 Rue Didouche"), "12 Rue Didouche");
+    }
+}
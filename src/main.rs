@@ -4,23 +4,58 @@ mod db;
 mod services;
 mod api;
 mod utils;
+mod middleware;
+mod metrics;
+#[cfg(test)]
+mod test_support;
 
-use actix_web::{web, App, HttpServer, middleware::Logger};
+use actix_web::{web, App, HttpServer, middleware::{from_fn, Logger}};
 use actix_cors::Cors;
-use sqlx::PgPool;
+use sqlx::postgres::PgPoolOptions;
 use std::sync::Arc;
 use std::time::Duration;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    // Initialize logger
-    env_logger::init();
+    // Initialize logger, tagging lines logged while handling a request with its id (and dry runs)
+    env_logger::Builder::from_default_env()
+        .format(|buf, record| {
+            use std::io::Write;
+            match middleware::current_request_id() {
+                Some(request_id) => writeln!(
+                    buf,
+                    "[{} {} {} request_id={}{}] {}",
+                    buf.timestamp(),
+                    record.level(),
+                    record.target(),
+                    request_id,
+                    if middleware::is_dry_run() { " dry_run" } else { "" },
+                    record.args()
+                ),
+                None => writeln!(
+                    buf,
+                    "[{} {} {}] {}",
+                    buf.timestamp(),
+                    record.level(),
+                    record.target(),
+                    record.args()
+                ),
+            }
+        })
+        .init();
+
+    metrics::init();
 
     // Load configuration
     let config = config::Config::from_env().expect("Failed to load configuration");
     
     // Setup database connection
-    let database_pool = PgPool::connect(&config.database.url)
+    let database_pool = PgPoolOptions::new()
+        .max_connections(config.database.max_connections)
+        .min_connections(config.database.min_connections)
+        .acquire_timeout(Duration::from_secs(config.database.acquire_timeout_seconds))
+        .idle_timeout(Duration::from_secs(config.database.idle_timeout_seconds))
+        .connect(&config.database.url)
         .await
         .expect("Failed to connect to database");
 
@@ -31,20 +66,35 @@ async fn main() -> std::io::Result<()> {
         .expect("Failed to run migrations");
 
     // Setup repository
-    let repository = Arc::new(db::Repository::new(database_pool));
+    let repository = Arc::new(db::Repository::new(database_pool, config.database.retry));
 
     // Setup services
+    let currency = utils::currency::CurrencyConverter::new(&config.currency);
     let recommendation_service = services::RecommendationService::new(
         repository.clone(),
-        Duration::from_secs(config.recommendation.cache_ttl_seconds),
+        &config.recommendation,
         config.cache.max_capacity,
+        currency.clone(),
+        config.feature_flags,
     );
     
-    let comparison_service = services::ComparisonService::new(repository.clone());
-    let quote_service = services::QuoteService::new(repository.clone());
+    let comparison_service = services::ComparisonService::new(repository.clone(), recommendation_service.clone());
+    let quote_service = services::QuoteService::new(repository.clone(), currency);
+
+    let rate_limiter = web::Data::new(middleware::RateLimiter::new(
+        config.rate_limit.enabled,
+        config.rate_limit.requests_per_minute,
+    ));
+
+    let api_key_auth = web::Data::new(middleware::ApiKeyAuth::new(
+        config.auth.api_keys.clone(),
+        config.auth.protected_paths.clone(),
+    ));
 
     let server_host = config.server.host.clone();
     let server_port = config.server.port;
+    let shutdown_timeout = config.server.shutdown_timeout_seconds;
+    let shutdown_repository = repository.clone();
 
     log::info!("Starting server at http://{}:{}", server_host, server_port);
 
@@ -60,14 +110,33 @@ async fn main() -> std::io::Result<()> {
             .app_data(web::Data::new(recommendation_service.clone()))
             .app_data(web::Data::new(comparison_service.clone()))
             .app_data(web::Data::new(quote_service.clone()))
+            .app_data(web::Data::from(repository.clone()))
+            .app_data(rate_limiter.clone())
+            .app_data(api_key_auth.clone())
+            .wrap(from_fn(middleware::api_key_auth))
+            // Outside auth, so rejected keys are throttled too
+            .wrap(from_fn(middleware::rate_limit))
             .wrap(cors)
-            .wrap(Logger::default())
+            .wrap(from_fn(middleware::track_http_metrics))
+            .wrap(from_fn(middleware::dry_run))
+            .wrap(from_fn(middleware::request_id))
+            .wrap(Logger::new(r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T request_id=%{x-request-id}o"#))
             .configure(api::configure_routes)
             .route("/health", web::get().to(health_check))
+            .route("/health/ready", web::get().to(readiness_check))
+            .route("/metrics", web::get().to(metrics_endpoint))
     })
     .bind(format!("{}:{}", server_host, server_port))?
+    .shutdown_timeout(shutdown_timeout)
     .run()
-    .await
+    .await?;
+
+    // Workers have drained in-flight requests by now; release connections cleanly
+    log::info!("Server stopped, closing database pool");
+    shutdown_repository.close().await;
+    log::info!("Shutdown complete");
+
+    Ok(())
 }
 
 async fn health_check() -> actix_web::Result<actix_web::HttpResponse> {
@@ -77,3 +146,57 @@ async fn health_check() -> actix_web::Result<actix_web::HttpResponse> {
         "version": env!("CARGO_PKG_VERSION")
     })))
 }
+
+async fn metrics_endpoint(
+    repository: web::Data<db::Repository>,
+) -> actix_web::Result<actix_web::HttpResponse> {
+    // Pool usage is sampled at scrape time rather than tracked on every query
+    let (size, idle) = repository.pool_stats();
+    metrics::record_pool_stats(size, idle);
+
+    match metrics::render() {
+        Ok(body) => Ok(actix_web::HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4")
+            .body(body)),
+        Err(e) => Ok(actix_web::HttpResponse::InternalServerError().json(models::ErrorResponse {
+            error: "Failed to render metrics".to_string(),
+            message: e.to_string(),
+        })),
+    }
+}
+
+async fn readiness_check(
+    repository: web::Data<db::Repository>,
+) -> actix_web::Result<actix_web::HttpResponse> {
+    // Liveness is covered by /health; readiness only passes when dependencies respond
+    let (database_ok, database_message) = match repository.ping().await {
+        Ok(()) => (true, "ok".to_string()),
+        Err(e) => {
+            log::warn!("Readiness check failed for database: {}", e);
+            (false, e.to_string())
+        }
+    };
+
+    let mut failed = Vec::new();
+    if !database_ok {
+        failed.push("database");
+    }
+
+    let body = serde_json::json!({
+        "status": if failed.is_empty() { "ready" } else { "not_ready" },
+        "timestamp": chrono::Utc::now(),
+        "checks": {
+            "database": {
+                "healthy": database_ok,
+                "message": database_message,
+            }
+        },
+        "failed": failed,
+    });
+
+    if failed.is_empty() {
+        Ok(actix_web::HttpResponse::Ok().json(body))
+    } else {
+        Ok(actix_web::HttpResponse::ServiceUnavailable().json(body))
+    }
+}
@@ -0,0 +1,93 @@
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::sync::LazyLock;
+
+pub static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::new);
+
+pub static HTTP_REQUESTS_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new("http_requests_total", "HTTP requests by route pattern and status"),
+        &["method", "path", "status"],
+    ))
+});
+
+pub static HTTP_REQUEST_DURATION_SECONDS: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by route pattern"),
+        &["method", "path"],
+    ))
+});
+
+pub static RECOMMENDATION_DURATION_SECONDS: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new("recommendation_duration_seconds", "Recommendation computation latency, including cache hits")
+            .buckets(vec![0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]),
+        &["kind"],
+    ))
+});
+
+pub static RECOMMENDATION_CACHE_REQUESTS_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new("recommendation_cache_requests_total", "Recommendation cache lookups by result"),
+        &["result"],
+    ))
+});
+
+pub static DB_POOL_CONNECTIONS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register(IntGaugeVec::new(
+        Opts::new("db_pool_connections", "Database pool connections by state, sampled at scrape time"),
+        &["state"],
+    ))
+});
+
+fn register<T>(metric: prometheus::Result<T>) -> T
+where
+    T: prometheus::core::Collector + Clone + 'static,
+{
+    let metric = metric.expect("Invalid metric definition");
+    REGISTRY
+        .register(Box::new(metric.clone()))
+        .expect("Metric registered twice");
+    metric
+}
+
+/// Registers every metric up front so that series appear before first use.
+pub fn init() {
+    LazyLock::force(&HTTP_REQUESTS_TOTAL);
+    LazyLock::force(&HTTP_REQUEST_DURATION_SECONDS);
+    LazyLock::force(&RECOMMENDATION_DURATION_SECONDS);
+    LazyLock::force(&RECOMMENDATION_CACHE_REQUESTS_TOTAL);
+    LazyLock::force(&DB_POOL_CONNECTIONS);
+}
+
+pub fn record_cache_lookup(hit: bool) {
+    if crate::middleware::is_dry_run() {
+        return;
+    }
+    RECOMMENDATION_CACHE_REQUESTS_TOTAL
+        .with_label_values(&[if hit { "hit" } else { "miss" }])
+        .inc();
+}
+
+pub fn observe_recommendation(kind: &str, elapsed: std::time::Duration) {
+    if crate::middleware::is_dry_run() {
+        return;
+    }
+    RECOMMENDATION_DURATION_SECONDS
+        .with_label_values(&[kind])
+        .observe(elapsed.as_secs_f64());
+}
+
+pub fn record_pool_stats(size: u32, idle: usize) {
+    let idle = idle as i64;
+    DB_POOL_CONNECTIONS.with_label_values(&["idle"]).set(idle);
+    DB_POOL_CONNECTIONS.with_label_values(&["in_use"]).set((size as i64 - idle).max(0));
+}
+
+/// Renders all registered metrics in the Prometheus text exposition format.
+pub fn render() -> Result<String, prometheus::Error> {
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer)?;
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
@@ -0,0 +1,119 @@
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web, Error, HttpResponse,
+};
+use crate::models::ErrorResponse;
+
+/// Checks API keys on configured path prefixes. `/health` is always public.
+pub struct ApiKeyAuth {
+    api_keys: Vec<String>,
+    protected_paths: Vec<String>,
+}
+
+impl ApiKeyAuth {
+    pub fn new(api_keys: Vec<String>, protected_paths: Vec<String>) -> Self {
+        if api_keys.is_empty() {
+            log::warn!("No API keys configured; API key authentication is disabled");
+        }
+
+        Self {
+            api_keys,
+            protected_paths,
+        }
+    }
+
+    pub fn is_protected(&self, path: &str) -> bool {
+        if self.api_keys.is_empty() || path.starts_with("/health") {
+            return false;
+        }
+
+        self.protected_paths.iter().any(|prefix| path.starts_with(prefix.as_str()))
+    }
+
+    pub fn is_valid_key(&self, key: &str) -> bool {
+        self.api_keys.iter().any(|valid| constant_time_eq(valid.as_bytes(), key.as_bytes()))
+    }
+}
+
+/// Reads the API key from `X-Api-Key`, or from an `Authorization: Bearer` header.
+pub fn extract_api_key(req: &ServiceRequest) -> Option<String> {
+    if let Some(key) = req.headers().get("X-Api-Key").and_then(|v| v.to_str().ok()) {
+        return Some(key.trim().to_string());
+    }
+
+    req.headers()
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|key| key.trim().to_string())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+pub async fn api_key_auth(
+    auth: web::Data<ApiKeyAuth>,
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    // Routing matches the percent-decoded path, so protection must check the
+    // same form; `req.path()` would let `/%61dmin` through
+    if !auth.is_protected(req.match_info().as_str()) {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+
+    match extract_api_key(&req) {
+        Some(key) if auth.is_valid_key(&key) => {
+            next.call(req).await.map(ServiceResponse::map_into_left_body)
+        }
+        provided => {
+            let message = if provided.is_some() {
+                "Invalid API key"
+            } else {
+                "Missing API key; send it in the X-Api-Key or Authorization: Bearer header"
+            };
+
+            let response = HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "Unauthorized".to_string(),
+                message: message.to_string(),
+            });
+            Ok(req.into_response(response).map_into_right_body())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, middleware::from_fn, test, App};
+
+    #[actix_web::test]
+    async fn percent_encoded_protected_paths_still_require_a_key() {
+        let auth = web::Data::new(ApiKeyAuth::new(vec!["secret".to_string()], vec!["/admin".to_string()]));
+        let app = test::init_service(
+            App::new()
+                .app_data(auth)
+                .wrap(from_fn(api_key_auth))
+                .route("/admin/flags", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        for path in ["/admin/flags", "/%61dmin/flags", "/%61%64%6D%69%6E/flags"] {
+            let response = test::call_service(&app, test::TestRequest::get().uri(path).to_request()).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", path);
+        }
+
+        let authorized = test::TestRequest::get()
+            .uri("/%61dmin/flags")
+            .insert_header(("X-Api-Key", "secret"))
+            .to_request();
+        assert_eq!(test::call_service(&app, authorized).await.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,116 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    middleware::Next,
+    web, Error, HttpResponse,
+};
+use crate::models::ErrorResponse;
+use serde::Deserialize;
+use std::future::Future;
+
+pub const DRY_RUN_HEADER: &str = "x-dry-run";
+
+tokio::task_local! {
+    static DRY_RUN: bool;
+}
+
+/// Whether the request being handled on this task is a dry run. Dry runs get
+/// real results but leave no trace in the cache or metrics.
+pub fn is_dry_run() -> bool {
+    DRY_RUN.try_with(|dry_run| *dry_run).unwrap_or(false)
+}
+
+/// Runs `future` with the dry-run flag in scope, for work moved onto another task.
+pub fn with_dry_run<F: Future>(dry_run: bool, future: F) -> impl Future<Output = F::Output> {
+    DRY_RUN.scope(dry_run, future)
+}
+
+/// Writes cannot be simulated, so their handlers refuse dry runs with a 400
+/// rather than quietly applying the change under an `X-Dry-Run: true` response.
+pub fn reject_dry_run_write() -> Option<HttpResponse> {
+    is_dry_run().then(|| {
+        HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Dry run not supported".to_string(),
+            message: "This request modifies data; send it without X-Dry-Run or ?dry_run".to_string(),
+        })
+    })
+}
+
+#[derive(Deserialize)]
+struct DryRunQuery {
+    dry_run: Option<String>,
+}
+
+fn is_truthy(value: &str) -> bool {
+    matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes")
+}
+
+pub async fn dry_run(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    // Either the header or a ?dry_run= query parameter enables it; both accept 1/true/yes
+    let from_header = req
+        .headers()
+        .get(DRY_RUN_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(is_truthy);
+    let from_query = web::Query::<DryRunQuery>::from_query(req.query_string())
+        .is_ok_and(|query| query.dry_run.as_deref().is_some_and(is_truthy));
+    let dry_run = from_header || from_query;
+
+    let mut response = DRY_RUN.scope(dry_run, next.call(req)).await?;
+
+    if dry_run {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(DRY_RUN_HEADER), HeaderValue::from_static("true"));
+    }
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, middleware::from_fn, test, App};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[actix_web::test]
+    async fn dry_run_writes_are_rejected_and_reads_echo_the_header() {
+        let writes = Arc::new(AtomicUsize::new(0));
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::from(writes.clone()))
+                .wrap(from_fn(dry_run))
+                .route("/read", web::get().to(|| async { HttpResponse::Ok().finish() }))
+                .route("/write", web::put().to(|writes: web::Data<AtomicUsize>| async move {
+                    if let Some(response) = reject_dry_run_write() {
+                        return response;
+                    }
+                    writes.fetch_add(1, Ordering::SeqCst);
+                    HttpResponse::Ok().finish()
+                })),
+        )
+        .await;
+
+        let read = test::TestRequest::get().uri("/read?dry_run=true").to_request();
+        let response = test::call_service(&app, read).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(DRY_RUN_HEADER).unwrap(), "true");
+
+        let write = test::TestRequest::put().uri("/write").insert_header((DRY_RUN_HEADER, "1")).to_request();
+        assert_eq!(test::call_service(&app, write).await.status(), StatusCode::BAD_REQUEST);
+        let write = test::TestRequest::put().uri("/write?dry_run=1").to_request();
+        assert_eq!(test::call_service(&app, write).await.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(writes.load(Ordering::SeqCst), 0);
+
+        let write = test::TestRequest::put().uri("/write").to_request();
+        let response = test::call_service(&app, write).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(DRY_RUN_HEADER).is_none());
+        assert_eq!(writes.load(Ordering::SeqCst), 1);
+    }
+}
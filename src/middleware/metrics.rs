@@ -0,0 +1,35 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    Error,
+};
+use crate::metrics::{HTTP_REQUESTS_TOTAL, HTTP_REQUEST_DURATION_SECONDS};
+use std::time::Instant;
+
+pub async fn track_http_metrics(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let start = Instant::now();
+    let method = req.method().to_string();
+    // Label by route pattern rather than raw path to keep cardinality bounded
+    let path = req.match_pattern().unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.call(req).await?;
+
+    // Synthetic probes and load tests should not show up as real traffic
+    if crate::middleware::is_dry_run() {
+        return Ok(response);
+    }
+
+    let status = response.status().as_u16().to_string();
+    HTTP_REQUESTS_TOTAL
+        .with_label_values(&[&method, &path, &status])
+        .inc();
+    HTTP_REQUEST_DURATION_SECONDS
+        .with_label_values(&[&method, &path])
+        .observe(start.elapsed().as_secs_f64());
+
+    Ok(response)
+}
@@ -0,0 +1,11 @@
+pub mod rate_limit;
+pub mod auth;
+pub mod metrics;
+pub mod request_id;
+pub mod dry_run;
+
+pub use rate_limit::*;
+pub use auth::*;
+pub use metrics::*;
+pub use request_id::*;
+pub use dry_run::*;
@@ -0,0 +1,181 @@
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web, Error, HttpResponse,
+};
+use crate::middleware::auth::{extract_api_key, ApiKeyAuth};
+use crate::models::ErrorResponse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Buckets idle for longer than this are dropped when the table is pruned.
+const IDLE_BUCKET_TTL: Duration = Duration::from_secs(300);
+/// How often idle buckets are pruned, so the sweep never runs per request.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+struct Buckets {
+    by_key: HashMap<String, TokenBucket>,
+    last_pruned: Instant,
+}
+
+/// Token-bucket limiter keyed by validated API key or peer IP. Each key may
+/// burst up to `requests_per_minute` requests and refills continuously at that rate.
+pub struct RateLimiter {
+    enabled: bool,
+    requests_per_minute: u32,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(enabled: bool, requests_per_minute: u32) -> Self {
+        Self {
+            enabled: enabled && requests_per_minute > 0,
+            requests_per_minute,
+            buckets: Mutex::new(Buckets {
+                by_key: HashMap::new(),
+                last_pruned: Instant::now(),
+            }),
+        }
+    }
+
+    /// Consumes a token for `key`, or returns how long to wait for the next one.
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let capacity = self.requests_per_minute as f64;
+        let refill_per_sec = capacity / 60.0;
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|poisoned| {
+            // Buckets are independent counters, so a panic mid-update costs at most one bucket's accuracy
+            log::error!("Rate limiter lock was poisoned by a panic; recovering");
+            self.buckets.clear_poison();
+            poisoned.into_inner()
+        });
+
+        if now.duration_since(buckets.last_pruned) >= PRUNE_INTERVAL {
+            buckets.by_key.retain(|_, bucket| now.duration_since(bucket.last_refill) < IDLE_BUCKET_TTL);
+            buckets.last_pruned = now;
+        }
+
+        let bucket = buckets.by_key.entry(key.to_string()).or_insert(TokenBucket {
+            tokens: capacity,
+            last_refill: now,
+        });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill_per_sec).min(capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / refill_per_sec))
+        }
+    }
+}
+
+/// Buckets a request by its API key when the key is valid, otherwise by the
+/// socket peer address. Unverified keys and forwarding headers are ignored so
+/// clients cannot pick a fresh bucket per request. Keys are only kept and
+/// logged as a fingerprint.
+fn client_key(req: &ServiceRequest, auth: &ApiKeyAuth) -> String {
+    if let Some(api_key) = extract_api_key(req).filter(|key| auth.is_valid_key(key)) {
+        let mut hasher = DefaultHasher::new();
+        api_key.hash(&mut hasher);
+        return format!("key:{:016x}", hasher.finish());
+    }
+
+    match req.peer_addr() {
+        Some(addr) => format!("ip:{}", addr.ip()),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+pub async fn rate_limit(
+    limiter: web::Data<RateLimiter>,
+    auth: web::Data<ApiKeyAuth>,
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    // Health probes must never be throttled
+    if req.path().starts_with("/health") {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+
+    let key = client_key(&req, &auth);
+    match limiter.check(&key) {
+        Ok(()) => next.call(req).await.map(ServiceResponse::map_into_left_body),
+        Err(retry_after) => {
+            let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            log::warn!("Rate limit exceeded for {} on {}", key, req.path());
+
+            let response = HttpResponse::TooManyRequests()
+                .insert_header(("Retry-After", retry_after_secs.to_string()))
+                .json(ErrorResponse {
+                    error: "Too many requests".to_string(),
+                    message: format!("Rate limit exceeded, retry in {} seconds", retry_after_secs),
+                });
+            Ok(req.into_response(response).map_into_right_body())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn auth() -> ApiKeyAuth {
+        ApiKeyAuth::new(vec!["valid-key".to_string()], vec!["/recommendations".to_string()])
+    }
+
+    #[test]
+    fn limiter_rejects_once_the_bucket_is_empty() {
+        let limiter = RateLimiter::new(true, 2);
+        assert!(limiter.check("ip:10.0.0.1").is_ok());
+        assert!(limiter.check("ip:10.0.0.1").is_ok());
+        assert!(limiter.check("ip:10.0.0.1").is_err());
+        assert!(limiter.check("ip:10.0.0.2").is_ok());
+    }
+
+    #[test]
+    fn unverified_keys_and_forwarded_ips_share_the_peer_bucket() {
+        let auth = auth();
+        let peer = "10.0.0.1:4000".parse().unwrap();
+
+        let anonymous = TestRequest::default().peer_addr(peer).to_srv_request();
+        let invalid_key = TestRequest::default()
+            .peer_addr(peer)
+            .insert_header(("X-Api-Key", "made-up"))
+            .insert_header(("X-Forwarded-For", "203.0.113.7"))
+            .to_srv_request();
+        assert_eq!(client_key(&anonymous, &auth), "ip:10.0.0.1");
+        assert_eq!(client_key(&invalid_key, &auth), "ip:10.0.0.1");
+    }
+
+    #[test]
+    fn valid_keys_are_bucketed_by_fingerprint() {
+        let auth = auth();
+        let request = TestRequest::default()
+            .peer_addr("10.0.0.1:4000".parse().unwrap())
+            .insert_header(("X-Api-Key", "valid-key"))
+            .to_srv_request();
+
+        let key = client_key(&request, &auth);
+        assert!(key.starts_with("key:"));
+        assert!(!key.contains("valid-key"));
+    }
+}
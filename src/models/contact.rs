@@ -1,6 +1,19 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use std::sync::LazyLock;
 use crate::models::property::NamedLocation;
 
+static EMAIL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[A-Za-z0-9._%+-]+@[A-Za-z0-9](?:[A-Za-z0-9-]*[A-Za-z0-9])?(?:\.[A-Za-z0-9](?:[A-Za-z0-9-]*[A-Za-z0-9])?)*\.[A-Za-z]{2,}$")
+        .expect("valid email regex")
+});
+
+// E.164: optional '+', country code not starting with 0, at most 15 digits
+static PHONE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\+?[1-9][0-9]{6,14}$").expect("valid phone regex")
+});
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Contact {
     pub id: i32,
@@ -12,6 +25,110 @@ pub struct Contact {
     pub max_area_sqm: i32,
     pub property_types: Vec<String>,
     pub min_rooms: i32,
+    #[serde(default)]
+    pub max_rooms: Option<i32>, // None means no upper limit
+    #[serde(default)]
+    pub required_amenities: Vec<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub phone: Option<String>,
+    #[serde(default)]
+    pub currency: Option<String>, // ISO 4217 code of the budget; None means the base currency
+    #[serde(default)]
+    pub excluded_locations: Vec<NamedLocation>, // Hard exclusion within each radius_km (default 1km)
+    #[serde(default)]
+    pub excluded_property_types: Vec<String>, // Also excludes subtypes, e.g. "apartment" excludes "studio"
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Every field of a contact that failed validation, so clients can fix them in one pass.
+#[derive(Debug, thiserror::Error)]
+#[error("invalid contact fields: {}", .errors.iter().map(|e| e.field.as_str()).collect::<Vec<_>>().join(", "))]
+pub struct ContactValidationError {
+    pub errors: Vec<FieldError>,
+}
+
+impl Contact {
+    /// Whether a room count is within the contact's min/max rooms.
+    pub fn wants_rooms(&self, rooms: i32) -> bool {
+        rooms >= self.min_rooms && self.max_rooms.is_none_or(|max_rooms| rooms <= max_rooms)
+    }
+
+    pub fn validate(&self) -> Result<(), ContactValidationError> {
+        let mut errors = Vec::new();
+        let mut fail = |field: &str, message: &str| errors.push(FieldError {
+            field: field.to_string(),
+            message: message.to_string(),
+        });
+
+        if self.name.trim().is_empty() {
+            fail("name", "Name must not be empty");
+        }
+        if let Some(email) = &self.email {
+            if !EMAIL_RE.is_match(email.trim()) {
+                fail("email", "Email address is not valid");
+            }
+        }
+        if let Some(phone) = &self.phone {
+            if !PHONE_RE.is_match(&normalize_phone(phone)) {
+                fail("phone", "Phone number must be in international format, e.g. +213555123456");
+            }
+        }
+        if let Some(currency) = &self.currency {
+            if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+                fail("currency", "Currency must be a three-letter ISO 4217 code, e.g. DZD");
+            }
+        }
+        for (i, location) in self.preferred_locations.iter().enumerate() {
+            if location.radius_km.is_some_and(|radius| radius <= 0.0 || !radius.is_finite()) {
+                fail(&format!("preferred_locations[{}].radius_km", i), "Radius must be a positive number of kilometres");
+            }
+            if location.weight.is_some_and(|weight| !(0.0..=1.0).contains(&weight)) {
+                fail(&format!("preferred_locations[{}].weight", i), "Weight must be between 0 and 1");
+            }
+        }
+        for (i, location) in self.excluded_locations.iter().enumerate() {
+            if location.radius_km.is_some_and(|radius| radius <= 0.0 || !radius.is_finite()) {
+                fail(&format!("excluded_locations[{}].radius_km", i), "Radius must be a positive number of kilometres");
+            }
+        }
+        if self.min_budget < 0.0 || self.min_budget > self.max_budget {
+            fail("max_budget", "Budget range must be non-negative with min_budget <= max_budget");
+        }
+        if self.min_area_sqm < 0 || self.min_area_sqm > self.max_area_sqm {
+            fail("max_area_sqm", "Area range must be non-negative with min_area_sqm <= max_area_sqm");
+        }
+        if self.max_rooms.is_some_and(|max_rooms| max_rooms < self.min_rooms) {
+            fail("max_rooms", "max_rooms must be at least min_rooms");
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ContactValidationError { errors })
+        }
+    }
+}
+
+/// Strips the separators people commonly type ("+213 (0)555-12.34") before validation.
+pub fn normalize_phone(phone: &str) -> String {
+    phone
+        .replace("(0)", "")
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+        .collect()
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -23,6 +140,16 @@ pub struct ContactPreferences {
     pub min_area_sqm: i32,
     pub max_area_sqm: i32,
     pub min_rooms: i32,
+    #[serde(default)]
+    pub max_rooms: Option<i32>,
+    #[serde(default)]
+    pub required_amenities: Vec<String>,
+    #[serde(default)]
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub excluded_locations: Vec<NamedLocation>,
+    #[serde(default)]
+    pub excluded_property_types: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -38,11 +165,160 @@ pub struct ContactFilter {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateContactRequest {
     pub name: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub phone: Option<String>,
     pub preferences: ContactPreferences,
 }
 
+impl CreateContactRequest {
+    pub fn into_contact(self) -> Contact {
+        Contact {
+            id: 0,
+            name: self.name,
+            preferred_locations: self.preferences.preferred_locations,
+            min_budget: self.preferences.min_budget,
+            max_budget: self.preferences.max_budget,
+            min_area_sqm: self.preferences.min_area_sqm,
+            max_area_sqm: self.preferences.max_area_sqm,
+            property_types: self.preferences.property_types,
+            min_rooms: self.preferences.min_rooms,
+            max_rooms: self.preferences.max_rooms,
+            required_amenities: self.preferences.required_amenities,
+            email: self.email,
+            phone: self.phone,
+            currency: self.preferences.currency,
+            excluded_locations: self.preferences.excluded_locations,
+            excluded_property_types: self.preferences.excluded_property_types,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+        }
+    }
+}
+
+/// Reads an optional field of a partial update as `None` when absent and
+/// `Some(None)` when explicitly `null`, so clients can clear a value.
+fn double_option<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Partial update: only the fields present are changed. Optional contact
+/// fields are cleared by sending `null`.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateContactRequest {
     pub name: Option<String>,
-    pub preferences: Option<ContactPreferences>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub email: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub phone: Option<Option<String>>,
+    pub preferences: Option<ContactPreferencesUpdate>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ContactPreferencesUpdate {
+    pub min_budget: Option<f64>,
+    pub max_budget: Option<f64>,
+    pub preferred_locations: Option<Vec<NamedLocation>>,
+    pub property_types: Option<Vec<String>>,
+    pub min_area_sqm: Option<i32>,
+    pub max_area_sqm: Option<i32>,
+    pub min_rooms: Option<i32>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub max_rooms: Option<Option<i32>>,
+    pub required_amenities: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub currency: Option<Option<String>>,
+    pub excluded_locations: Option<Vec<NamedLocation>>,
+    pub excluded_property_types: Option<Vec<String>>,
+}
+
+impl UpdateContactRequest {
+    pub fn apply_to(self, contact: &mut Contact) {
+        if let Some(name) = self.name {
+            contact.name = name;
+        }
+        if let Some(email) = self.email {
+            contact.email = email;
+        }
+        if let Some(phone) = self.phone {
+            contact.phone = phone;
+        }
+
+        let preferences = self.preferences.unwrap_or_default();
+        if let Some(min_budget) = preferences.min_budget {
+            contact.min_budget = min_budget;
+        }
+        if let Some(max_budget) = preferences.max_budget {
+            contact.max_budget = max_budget;
+        }
+        if let Some(preferred_locations) = preferences.preferred_locations {
+            contact.preferred_locations = preferred_locations;
+        }
+        if let Some(property_types) = preferences.property_types {
+            contact.property_types = property_types;
+        }
+        if let Some(min_area_sqm) = preferences.min_area_sqm {
+            contact.min_area_sqm = min_area_sqm;
+        }
+        if let Some(max_area_sqm) = preferences.max_area_sqm {
+            contact.max_area_sqm = max_area_sqm;
+        }
+        if let Some(min_rooms) = preferences.min_rooms {
+            contact.min_rooms = min_rooms;
+        }
+        if let Some(max_rooms) = preferences.max_rooms {
+            contact.max_rooms = max_rooms;
+        }
+        if let Some(required_amenities) = preferences.required_amenities {
+            contact.required_amenities = required_amenities;
+        }
+        if let Some(currency) = preferences.currency {
+            contact.currency = currency;
+        }
+        if let Some(excluded_locations) = preferences.excluded_locations {
+            contact.excluded_locations = excluded_locations;
+        }
+        if let Some(excluded_property_types) = preferences.excluded_property_types {
+            contact.excluded_property_types = excluded_property_types;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn update(json: &str) -> UpdateContactRequest {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn null_clears_optional_fields_and_absent_keeps_them() {
+        let mut contact = test_support::contact(1);
+        contact.email = Some("amina@example.com".to_string());
+        contact.phone = Some("+213555123456".to_string());
+        contact.max_rooms = Some(3);
+        contact.currency = Some("EUR".to_string());
+
+        update(r#"{"name": "Amina"}"#).apply_to(&mut contact);
+        assert_eq!(contact.email.as_deref(), Some("amina@example.com"));
+        assert_eq!(contact.max_rooms, Some(3));
+
+        update(r#"{"email": null, "phone": null, "preferences": {"max_rooms": null, "currency": null}}"#)
+            .apply_to(&mut contact);
+        assert_eq!(contact.email, None);
+        assert_eq!(contact.phone, None);
+        assert_eq!(contact.max_rooms, None);
+        assert_eq!(contact.currency, None);
+
+        update(r#"{"preferences": {"max_rooms": 4}}"#).apply_to(&mut contact);
+        assert_eq!(contact.max_rooms, Some(4));
+    }
 }
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Property {
@@ -9,6 +10,79 @@ pub struct Property {
     pub area_sqm: i32,
     pub property_type: String,
     pub number_of_rooms: i32,
+    #[serde(default)]
+    pub amenities: Vec<String>,
+    #[serde(default = "Utc::now")]
+    pub listed_at: DateTime<Utc>,
+    #[serde(default)]
+    pub currency: Option<String>, // ISO 4217 code of `price`; None means the base currency
+    #[serde(default)]
+    pub availability: PropertyAvailability,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>, // Set by DELETE; soft-deleted rows are hidden from normal queries
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PropertyAvailability {
+    #[default]
+    Available,
+    UnderOffer,
+    Sold,
+    Rented,
+}
+
+impl PropertyAvailability {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Available => "available",
+            Self::UnderOffer => "under_offer",
+            Self::Sold => "sold",
+            Self::Rented => "rented",
+        }
+    }
+}
+
+impl std::str::FromStr for PropertyAvailability {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "available" => Ok(Self::Available),
+            "under_offer" => Ok(Self::UnderOffer),
+            "sold" => Ok(Self::Sold),
+            "rented" => Ok(Self::Rented),
+            other => Err(format!("Unknown availability '{}'", other)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SimilarProperty {
+    pub property: Property,
+    pub similarity_score: f64,
+    pub distance_km: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateAvailabilityRequest {
+    pub availability: PropertyAvailability,
+}
+
+impl Property {
+    /// Price divided by area, or `None` for listings without a usable area or
+    /// price so callers never see infinity or NaN.
+    pub fn price_per_sqm(&self) -> Option<f64> {
+        if self.area_sqm > 0 && self.price.is_finite() {
+            Some(self.price / self.area_sqm as f64)
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -36,8 +110,15 @@ pub struct NamedLocation {
     pub name: String,
     pub lat: f64,
     pub lon: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub radius_km: Option<f64>, // Distance considered "close"; defaults to 15km
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<f64>, // Relative importance in [0, 1]; defaults to 1.0
 }
 
+/// Largest page `GET /properties` will return.
+pub const MAX_PAGE_SIZE: i64 = 100;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PropertyFilter {
     pub property_type: Option<String>,
@@ -47,7 +128,101 @@ pub struct PropertyFilter {
     pub max_area: Option<i32>,
     pub min_rooms: Option<i32>,
     pub max_rooms: Option<i32>,
-    pub address: Option<String>,
+    pub address: Option<String>, // Case-insensitive substring match
+    pub availability: Option<PropertyAvailability>,
+    pub lat: Option<f64>, // lat, lon and radius_km must be given together
+    pub lon: Option<f64>,
+    pub radius_km: Option<f64>,
+    #[serde(default)]
+    pub include_deleted: bool, // Opt-in for analytics; soft-deleted rows carry their deleted_at
+    #[serde(default)]
+    pub sort_by: PropertySort,
+    pub page: Option<i64>,      // 1-based, defaults to 1
+    pub page_size: Option<i64>, // Defaults to 20, at most MAX_PAGE_SIZE
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PropertySort {
+    #[default]
+    Newest,
+    PriceAsc,
+    PriceDesc,
+    AreaAsc,
+    AreaDesc,
+    Distance, // Requires a location
+}
+
+impl PropertyFilter {
+    pub fn page(&self) -> i64 {
+        self.page.unwrap_or(1)
+    }
+
+    pub fn page_size(&self) -> i64 {
+        self.page_size.unwrap_or(20)
+    }
+
+    /// Rows skipped before the requested page; `None` if it overflows.
+    pub fn offset(&self) -> Option<i64> {
+        (self.page() - 1).checked_mul(self.page_size())
+    }
+
+    /// Centre and radius of the location filter, if one was given.
+    pub fn location(&self) -> Option<(f64, f64, f64)> {
+        Some((self.lat?, self.lon?, self.radius_km?))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.page() < 1 {
+            return Err("page must be at least 1".to_string());
+        }
+        if !(1..=MAX_PAGE_SIZE).contains(&self.page_size()) {
+            return Err(format!("page_size must be between 1 and {}", MAX_PAGE_SIZE));
+        }
+        if self.offset().is_none() {
+            return Err("page is too large".to_string());
+        }
+        if let (Some(min), Some(max)) = (self.min_price, self.max_price) {
+            if min > max {
+                return Err("min_price must not exceed max_price".to_string());
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_area, self.max_area) {
+            if min > max {
+                return Err("min_area must not exceed max_area".to_string());
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_rooms, self.max_rooms) {
+            if min > max {
+                return Err("min_rooms must not exceed max_rooms".to_string());
+            }
+        }
+
+        let location_parts = [self.lat, self.lon, self.radius_km].iter().filter(|v| v.is_some()).count();
+        if location_parts != 0 && location_parts != 3 {
+            return Err("lat, lon and radius_km must be given together".to_string());
+        }
+        if let Some((lat, lon, radius_km)) = self.location() {
+            if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+                return Err("lat or lon is out of range".to_string());
+            }
+            if radius_km <= 0.0 || !radius_km.is_finite() {
+                return Err("radius_km must be positive".to_string());
+            }
+        } else if self.sort_by == PropertySort::Distance {
+            return Err("sort_by=distance requires lat, lon and radius_km".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PropertySearchResponse {
+    pub properties: Vec<Property>,
+    pub total_count: i64,
+    pub page: i64,
+    pub page_size: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -67,6 +242,8 @@ pub struct ComparisonMetrics {
     pub area_difference_percentage: f64,
     pub location_distance_km: f64,
     pub overall_similarity_score: f64,
+    pub property1_price_per_sqm: Option<f64>,
+    pub property2_price_per_sqm: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -83,7 +260,7 @@ pub struct PriceAnalysis {
     pub cheaper_property: i32,
     pub price_savings: f64,
     pub affordability_rating: String,
-    pub price_per_sqm_comparison: (f64, f64), // (property1_price_per_sqm, property2_price_per_sqm)
+    pub price_per_sqm_comparison: (Option<f64>, Option<f64>), // (property1, property2); None without a usable area
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -112,7 +289,7 @@ pub struct FeatureAnalysis {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ValueAnalysis {
     pub better_value_property: i32,
-    pub value_score: (f64, f64), // (property1_value_score, property2_value_score)
+    pub value_score: (Option<f64>, Option<f64>), // (property1, property2); None without a usable area
     pub investment_potential: String,
 }
 
@@ -124,3 +301,25 @@ pub struct ComparisonRecommendation {
     pub considerations: Vec<String>,
     pub summary: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::web::Query;
+
+    fn filter(query: &str) -> PropertyFilter {
+        Query::<PropertyFilter>::from_query(query).unwrap().into_inner()
+    }
+
+    #[test]
+    fn huge_pages_are_rejected_instead_of_overflowing() {
+        assert_eq!(filter("page=3&page_size=50").offset(), Some(100));
+
+        let huge = filter("page=9223372036854775807");
+        assert_eq!(huge.offset(), None);
+        assert_eq!(huge.validate(), Err("page is too large".to_string()));
+
+        assert!(filter("page=0").validate().is_err());
+        assert!(filter("page_size=101").validate().is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use crate::config::ScoringWeights;
 use crate::models::{contact::Contact, property::Property};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,8 +18,18 @@ pub struct RecommendationExplanation {
     pub overall_score: f64,
     pub budget_match: BudgetMatch,
     pub location_match: LocationMatch,
-    pub property_type_match: bool,
+    pub property_type_match: bool, // Same type as one the contact asked for, or a synonym of it
+    #[serde(default)]
+    pub property_type_score: f64, // 1.0 exact, partial for related types, 0.0 unrelated
     pub size_match: SizeMatch,
+    #[serde(default)]
+    pub amenity_match: AmenityMatch,
+    #[serde(default)]
+    pub freshness: FreshnessBoost,
+    #[serde(default)]
+    pub price_per_sqm: Option<f64>, // In the base currency; None when the listing has no usable area
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub excluded: Option<String>, // Set when the contact's exclusions filter this property out
     pub reasons: Vec<String>,
 }
 
@@ -42,6 +54,31 @@ pub struct SizeMatch {
     pub score: f64,
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FreshnessBoost {
+    pub days_listed: f64,
+    pub score: f64,
+    pub contribution: f64, // Change in overall score caused by the boost; negative for stale listings
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AmenityMatch {
+    pub matched: Vec<String>,
+    pub missing: Vec<String>,
+    pub score: f64,
+}
+
+/// Ordering of returned recommendations. Score cutoffs select candidates
+/// first; `top_k` and `limit` then keep the leading results in this order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecommendationSort {
+    #[default]
+    Score,
+    PricePerSqmAsc,
+    PricePerSqmDesc,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RecommendationRequest {
     pub contact_id: i32,
@@ -54,6 +91,8 @@ pub struct BulkRecommendationRequest {
     pub limit_per_property: Option<usize>,
     pub min_score: Option<f64>,
     pub property_ids: Option<Vec<i32>>,
+    #[serde(default)]
+    pub include_unavailable: bool, // Only applies when property_ids is omitted
     pub top_k: Option<usize>,
     pub top_percentile: Option<f64>,
     pub score_threshold_percentile: Option<f64>,
@@ -64,31 +103,13 @@ pub struct BulkRecommendationRequest {
 }
 
 impl BulkRecommendationRequest {
-    pub fn get_weights(&self) -> (f64, f64, f64, f64) {
-        let budget = self.budget_weight.unwrap_or(0.3);
-        let location = self.location_weight.unwrap_or(0.25);
-        let property_type = self.property_type_weight.unwrap_or(0.2);
-        let size = self.size_weight.unwrap_or(0.25);
-        
-        (budget, location, property_type, size)
-    }
-    
-    pub fn validate_weights(&self) -> Result<(), String> {
-        if let (Some(b), Some(l), Some(p), Some(s)) = (
+    pub fn resolve_weights(&self, defaults: ScoringWeights) -> Result<ScoringWeights, String> {
+        defaults.with_overrides(
             self.budget_weight,
             self.location_weight,
             self.property_type_weight,
             self.size_weight,
-        ) {
-            let sum = b + l + p + s;
-            if (sum - 1.0).abs() > 0.001 {
-                return Err(format!("Weights must sum to 1.0, got {:.3}", sum));
-            }
-            if b < 0.0 || l < 0.0 || p < 0.0 || s < 0.0 {
-                return Err("All weights must be non-negative".to_string());
-            }
-        }
-        Ok(())
+        )
     }
 }
 
@@ -100,6 +121,47 @@ pub struct BulkRecommendationResponse {
     pub processing_time_ms: u64,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchContactRecommendationRequest {
+    pub contact_ids: Vec<i32>,
+    pub limit: Option<usize>,
+    pub min_score: Option<f64>,
+    pub top_k: Option<usize>,
+    pub top_percentile: Option<f64>,
+    pub score_threshold_percentile: Option<f64>,
+    pub max_distance_km: Option<f64>,
+    pub excluded_property_types: Option<Vec<String>>, // Replaces each contact's stored type exclusions
+    #[serde(default)]
+    pub include_unavailable: bool, // Also score sold, rented and under-offer listings
+    #[serde(default)]
+    pub sort_by: RecommendationSort,
+    pub budget_weight: Option<f64>,
+    pub location_weight: Option<f64>,
+    pub property_type_weight: Option<f64>,
+    pub size_weight: Option<f64>,
+}
+
+impl BatchContactRecommendationRequest {
+    pub fn resolve_weights(&self, defaults: ScoringWeights) -> Result<ScoringWeights, String> {
+        defaults.with_overrides(
+            self.budget_weight,
+            self.location_weight,
+            self.property_type_weight,
+            self.size_weight,
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchContactRecommendationResponse {
+    pub results: HashMap<i32, RecommendationResponse>,
+    pub errors: HashMap<i32, String>, // Unknown or failed contacts, keyed by contact id
+    pub total_contacts: usize,
+    pub successful_contacts: usize,
+    pub failed_contacts: usize,
+    pub processing_time_ms: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PropertyRecommendations {
     pub property_id: i32,
@@ -121,4 +183,48 @@ pub struct RecommendationResponse {
     pub recommendations: Vec<Recommendation>,
     pub total_count: usize,
     pub processing_time_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_quality_threshold: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>, // "no_strong_matches" when nothing cleared the quality floor
+}
+
+/// Most properties one contact comparison may rank.
+pub const MAX_PROPERTIES_PER_CONTACT_COMPARISON: usize = 20;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContactComparisonRequest {
+    pub contact_id: i32,
+    pub property_ids: Vec<i32>,
+    pub budget_weight: Option<f64>,
+    pub location_weight: Option<f64>,
+    pub property_type_weight: Option<f64>,
+    pub size_weight: Option<f64>,
+}
+
+impl ContactComparisonRequest {
+    pub fn resolve_weights(&self, defaults: ScoringWeights) -> Result<ScoringWeights, String> {
+        defaults.with_overrides(
+            self.budget_weight,
+            self.location_weight,
+            self.property_type_weight,
+            self.size_weight,
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContactPropertyRanking {
+    pub rank: usize, // 1 is the best match for the contact
+    pub property: Property,
+    pub score: f64,
+    pub score_gap_to_best: f64,
+    pub explanation: RecommendationExplanation,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContactComparisonResponse {
+    pub contact_id: i32,
+    pub rankings: Vec<ContactPropertyRanking>,
+    pub missing_property_ids: Vec<i32>, // Requested ids that do not exist
 }
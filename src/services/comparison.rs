@@ -1,17 +1,23 @@
+use crate::config::ScoringWeights;
 use crate::db::Repository;
 use crate::models::*;
-use crate::utils::scoring::calculate_distance_km;
+use crate::services::RecommendationService;
+use crate::utils::scoring::{calculate_distance_km, compare_scores_desc};
 use anyhow::Result;
 use std::sync::Arc;
 
+/// Upper bound on `k` for similar-property lookups.
+pub const MAX_SIMILAR_PROPERTIES: usize = 50;
+
 #[derive(Clone)]
 pub struct ComparisonService {
     repository: Arc<Repository>,
+    recommendations: RecommendationService, // Scores properties for a contact the same way recommendations do
 }
 
 impl ComparisonService {
-    pub fn new(repository: Arc<Repository>) -> Self {
-        Self { repository }
+    pub fn new(repository: Arc<Repository>, recommendations: RecommendationService) -> Self {
+        Self { repository, recommendations }
     }
 
     pub async fn compare_properties(
@@ -26,14 +32,18 @@ impl ComparisonService {
         let property2 = self.repository.get_property_by_id(property2_id).await?
             .ok_or_else(|| anyhow::anyhow!("Second property not found"))?;
 
+        // Metrics and analyses compare prices in the base currency
+        let base1 = self.in_base_currency(&property1)?;
+        let base2 = self.in_base_currency(&property2)?;
+
         // Calculate comparison metrics
-        let comparison_metrics = self.calculate_comparison_metrics(&property1, &property2);
+        let comparison_metrics = self.calculate_comparison_metrics(&base1, &base2);
         
         // Generate detailed analysis
-        let detailed_analysis = self.generate_detailed_analysis(&property1, &property2, &comparison_metrics);
+        let detailed_analysis = self.generate_detailed_analysis(&base1, &base2, &comparison_metrics);
         
         // Generate recommendation
-        let recommendation = self.generate_comparison_recommendation(&property1, &property2, &comparison_metrics);
+        let recommendation = self.generate_comparison_recommendation(&base1, &base2, &comparison_metrics);
 
         Ok(PropertyComparison {
             property1,
@@ -44,6 +54,123 @@ impl ComparisonService {
         })
     }
 
+    /// A copy of the property priced in the base currency, so listings from
+    /// different markets compare meaningfully. Fails rather than mixing
+    /// currencies when the listing's currency has no configured rate.
+    fn in_base_currency(&self, property: &Property) -> Result<Property> {
+        let currency = self.recommendations.currency();
+        let price = currency
+            .to_base(property.price, property.currency.as_deref())
+            .ok_or_else(|| anyhow::anyhow!(
+                "No exchange rate for property {} currency {}",
+                property.id,
+                property.currency.as_deref().unwrap_or_default()
+            ))?;
+        Ok(Property {
+            price,
+            currency: Some(currency.base().to_string()),
+            ..property.clone()
+        })
+    }
+
+    /// Finds the `k` available properties most similar to the given one, using
+    /// the same similarity score as pairwise comparisons. Returns `None` when
+    /// the property does not exist.
+    pub async fn find_similar(&self, property_id: i32, k: usize) -> Result<Option<Vec<SimilarProperty>>> {
+        let property = match self.repository.get_property_by_id(property_id).await? {
+            Some(property) => property,
+            None => return Ok(None),
+        };
+
+        let candidates = self.repository.get_all_active_properties().await?;
+        let mut similar: Vec<SimilarProperty> = candidates
+            .into_iter()
+            .filter(|candidate| candidate.id != property.id)
+            .filter(|candidate| candidate.availability == PropertyAvailability::Available)
+            .map(|candidate| {
+                let distance_km = calculate_distance_km(
+                    property.location.lat,
+                    property.location.lon,
+                    candidate.location.lat,
+                    candidate.location.lon,
+                );
+                let feature_similarity = self.calculate_feature_similarity(&property, &candidate);
+                let similarity_score = self.calculate_overall_similarity(
+                    &property,
+                    &candidate,
+                    feature_similarity,
+                    distance_km,
+                );
+                SimilarProperty {
+                    property: candidate,
+                    similarity_score,
+                    distance_km,
+                }
+            })
+            .collect();
+
+        similar.sort_by(|a, b| {
+            compare_scores_desc(a.similarity_score, b.similarity_score)
+                .then_with(|| a.property.id.cmp(&b.property.id))
+        });
+        similar.truncate(k);
+
+        Ok(Some(similar))
+    }
+
+    /// Scores each property for one contact and ranks them, best first.
+    /// Unavailable or excluded properties are still scored, since the caller
+    /// picked them, and their explanation says why they would be filtered.
+    /// Returns `None` when the contact does not exist.
+    pub async fn compare_for_contact(
+        &self,
+        contact_id: i32,
+        property_ids: &[i32],
+        weights: ScoringWeights,
+    ) -> Result<Option<ContactComparisonResponse>> {
+        let contact = match self.repository.get_contact_by_id(contact_id).await? {
+            Some(contact) => contact,
+            None => return Ok(None),
+        };
+
+        let properties = self.repository.get_properties_by_ids(property_ids).await?;
+        let mut missing_property_ids: Vec<i32> = property_ids
+            .iter()
+            .copied()
+            .filter(|id| !properties.iter().any(|property| property.id == *id))
+            .collect();
+        missing_property_ids.sort_unstable();
+        missing_property_ids.dedup();
+
+        let flags = self.recommendations.feature_flags();
+        let mut scored: Vec<Recommendation> = properties
+            .iter()
+            .map(|property| self.recommendations.calculate_recommendation(&contact, property, &weights, &flags))
+            .collect();
+        scored.sort_by(|a, b| {
+            compare_scores_desc(a.score, b.score).then_with(|| a.property.id.cmp(&b.property.id))
+        });
+
+        let best_score = scored.first().map(|r| r.score).unwrap_or(0.0);
+        let rankings = scored
+            .into_iter()
+            .enumerate()
+            .map(|(i, recommendation)| ContactPropertyRanking {
+                rank: i + 1,
+                score_gap_to_best: best_score - recommendation.score,
+                score: recommendation.score,
+                property: recommendation.property,
+                explanation: recommendation.explanation,
+            })
+            .collect();
+
+        Ok(Some(ContactComparisonResponse {
+            contact_id,
+            rankings,
+            missing_property_ids,
+        }))
+    }
+
     fn calculate_comparison_metrics(&self, property1: &Property, property2: &Property) -> ComparisonMetrics {
         // Price comparison
         let price_difference = property2.price - property1.price;
@@ -87,6 +214,8 @@ impl ComparisonService {
             area_difference_percentage,
             location_distance_km,
             overall_similarity_score,
+            property1_price_per_sqm: property1.price_per_sqm(),
+            property2_price_per_sqm: property2.price_per_sqm(),
         }
     }
 
@@ -223,14 +352,11 @@ impl ComparisonService {
             "Major price difference".to_string()
         };
 
-        let property1_price_per_sqm = property1.price / property1.area_sqm as f64;
-        let property2_price_per_sqm = property2.price / property2.area_sqm as f64;
-
         PriceAnalysis {
             cheaper_property,
             price_savings,
             affordability_rating,
-            price_per_sqm_comparison: (property1_price_per_sqm, property2_price_per_sqm),
+            price_per_sqm_comparison: (property1.price_per_sqm(), property2.price_per_sqm()),
         }
     }
 
@@ -335,14 +461,30 @@ impl ComparisonService {
     }
 
     fn analyze_value_comparison(&self, property1: &Property, property2: &Property) -> ValueAnalysis {
-        // Calculate value scores based on price per square meter and features
-        let property1_price_per_sqm = property1.price / property1.area_sqm as f64;
-        let property2_price_per_sqm = property2.price / property2.area_sqm as f64;
-        
+        // Calculate value scores based on price per square meter and features.
+        // A listing without a usable area has no price per sqm, so it gets no
+        // value score and cannot come out as the better value.
+        let (property1_price_per_sqm, property2_price_per_sqm) = match (property1.price_per_sqm(), property2.price_per_sqm()) {
+            (Some(price1), Some(price2)) => (price1, price2),
+            (price1, price2) => {
+                let investment_potential = match (price1, price2) {
+                    (Some(_), None) => "Property 2 has no usable area, so its value for money cannot be assessed",
+                    (None, Some(_)) => "Property 1 has no usable area, so its value for money cannot be assessed",
+                    _ => "Neither property has a usable area, so value for money cannot be compared",
+                };
+                return ValueAnalysis {
+                    better_value_property: if price1.is_none() && price2.is_some() { property2.id } else { property1.id },
+                    value_score: (None, None),
+                    investment_potential: investment_potential.to_string(),
+                };
+            }
+        };
+
         // Simple value scoring: lower price per sqm = higher value score
         let max_price_per_sqm = property1_price_per_sqm.max(property2_price_per_sqm);
-        let property1_value_score = 1.0 - (property1_price_per_sqm / max_price_per_sqm - 0.5).abs();
-        let property2_value_score = 1.0 - (property2_price_per_sqm / max_price_per_sqm - 0.5).abs();
+        let relative = |price_per_sqm: f64| if max_price_per_sqm > 0.0 { price_per_sqm / max_price_per_sqm } else { 0.5 };
+        let property1_value_score = 1.0 - (relative(property1_price_per_sqm) - 0.5).abs();
+        let property2_value_score = 1.0 - (relative(property2_price_per_sqm) - 0.5).abs();
 
         let better_value_property = if property1_value_score >= property2_value_score {
             property1.id
@@ -360,7 +502,7 @@ impl ComparisonService {
 
         ValueAnalysis {
             better_value_property,
-            value_score: (property1_value_score, property2_value_score),
+            value_score: (Some(property1_value_score), Some(property2_value_score)),
             investment_potential,
         }
     }
@@ -442,3 +584,51 @@ impl ComparisonService {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FeatureFlags;
+    use crate::test_support;
+
+    fn service() -> ComparisonService {
+        ComparisonService::new(test_support::repository(), test_support::recommendation_service(FeatureFlags::default()))
+    }
+
+    #[tokio::test]
+    async fn listings_without_area_are_never_the_better_value() {
+        let service = service();
+        let mut no_area = test_support::property(1, 100_000.0);
+        no_area.area_sqm = 0;
+        let priced = test_support::property(2, 150_000.0);
+
+        let price = service.analyze_price_comparison(&no_area, &priced);
+        assert_eq!(price.price_per_sqm_comparison, (None, Some(1500.0)));
+
+        let value = service.analyze_value_comparison(&no_area, &priced);
+        assert_eq!(value.better_value_property, 2);
+        assert_eq!(value.value_score, (None, None));
+
+        let value = service.analyze_value_comparison(&priced, &test_support::property(3, 120_000.0));
+        assert!(value.value_score.0.is_some() && value.value_score.1.is_some());
+    }
+
+    #[tokio::test]
+    async fn comparisons_price_both_listings_in_the_base_currency() {
+        let service = service();
+        let mut in_euros = test_support::property(1, 1_000.0);
+        in_euros.currency = Some("EUR".to_string());
+        let in_dinars = test_support::property(2, 160_000.0);
+
+        let base = service.in_base_currency(&in_euros).unwrap();
+        assert_eq!(base.price, 150_000.0);
+        assert_eq!(base.currency.as_deref(), Some("DZD"));
+
+        let metrics = service.calculate_comparison_metrics(&base, &service.in_base_currency(&in_dinars).unwrap());
+        assert_eq!(metrics.price_difference, 10_000.0);
+        assert_eq!(metrics.property1_price_per_sqm, Some(1_500.0));
+
+        in_euros.currency = Some("GBP".to_string());
+        assert!(service.in_base_currency(&in_euros).is_err());
+    }
+}
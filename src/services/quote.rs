@@ -1,12 +1,23 @@
 use crate::db::Repository;
 use crate::models::*;
+use crate::utils::currency::CurrencyConverter;
 use anyhow::Result;
 use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 
+/// Quote failures the API reports as client errors rather than 500s.
+#[derive(Debug, thiserror::Error)]
+pub enum QuoteError {
+    #[error("{0} not found")]
+    NotFound(&'static str),
+    #[error("{0}")]
+    InvalidInput(String),
+}
+
 #[derive(Clone)]
 pub struct QuoteService {
     repository: Arc<Repository>,
+    currency: Arc<CurrencyConverter>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -118,9 +129,89 @@ pub struct ComparisonRecommendation {
     pub score_difference: f64,
 }
 
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct QuoteOptions {
+    pub discount_percentage: Option<f64>,
+    pub currency: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuoteLineItem {
+    pub description: String,
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Quote {
+    pub property: Property,
+    pub currency: String,
+    pub base_price: f64,
+    pub discount_percentage: f64,
+    pub discount_amount: f64,
+    pub fees: Vec<QuoteLineItem>,
+    pub taxes: Vec<QuoteLineItem>,
+    pub total: f64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AffordabilityOptions {
+    pub interest_rate: Option<f64>,
+    pub term_years: Option<i32>,
+    pub down_payment_percentage: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AffordabilityReport {
+    pub contact_id: i32,
+    pub property_id: i32,
+    pub currency: String, // The contact's budget currency; every amount below is in it
+    pub property_price: f64,
+    pub budget_min: f64,
+    pub budget_max: f64,
+    pub is_affordable: bool,
+    pub budget_headroom: f64, // Negative when the property exceeds the maximum budget
+    pub budget_position: String,
+    pub down_payment: f64,
+    pub loan_amount: f64,
+    pub interest_rate: f64,
+    pub loan_term_years: i32,
+    pub estimated_monthly_payment: f64,
+    pub total_interest: f64,
+}
+
 impl QuoteService {
-    pub fn new(repository: Arc<Repository>) -> Self {
-        Self { repository }
+    pub fn new(repository: Arc<Repository>, currency: CurrencyConverter) -> Self {
+        Self {
+            repository,
+            currency: Arc::new(currency),
+        }
+    }
+
+    /// Resolves the currency a quote is expressed in: the requested one, else
+    /// the listing's own, else the base currency.
+    fn quote_currency(&self, requested: Option<&str>, property: &Property) -> Result<String, QuoteError> {
+        let currency = requested
+            .or(property.currency.as_deref())
+            .map(|code| code.trim().to_uppercase())
+            .unwrap_or_else(|| self.currency.base().to_string());
+        if !self.currency.supports(&currency) {
+            return Err(QuoteError::InvalidInput(format!("Unsupported currency: {}", currency)));
+        }
+        Ok(currency)
+    }
+
+    /// Converts a listing's price, failing rather than mixing currencies when
+    /// the listing's currency has no configured rate.
+    fn convert_price(&self, property: &Property, to: Option<&str>) -> Result<f64> {
+        self.currency
+            .convert(property.price, property.currency.as_deref(), to)
+            .ok_or_else(|| anyhow::anyhow!(
+                "No exchange rate for property {} currency {}",
+                property.id,
+                property.currency.as_deref().unwrap_or_default()
+            ))
     }
 
     pub async fn generate_property_quote(&self, request: QuoteRequest) -> Result<QuoteResponse> {
@@ -166,6 +257,133 @@ impl QuoteService {
         })
     }
 
+    pub async fn build_quote(&self, property_id: i32, options: QuoteOptions) -> Result<Quote> {
+        let discount_percentage = options.discount_percentage.unwrap_or(0.0);
+        if !(0.0..=100.0).contains(&discount_percentage) {
+            return Err(QuoteError::InvalidInput("Discount percentage must be between 0 and 100".to_string()).into());
+        }
+
+        let property = self.repository.get_property_by_id(property_id).await?
+            .ok_or(QuoteError::NotFound("Property"))?;
+        let currency = self.quote_currency(options.currency.as_deref(), &property)?;
+
+        // Fees and taxes are defined in the base currency, so the quote is
+        // built there and every amount converted once at the end
+        let base_price = self.convert_price(&property, None)?;
+        let discount_amount = base_price * discount_percentage / 100.0;
+        let discounted_price = base_price - discount_amount;
+
+        // Standard fees are stored in cents; percentage-based ones are computed below
+        let mut fees: Vec<QuoteLineItem> = Self::get_standard_additional_costs()
+            .into_iter()
+            .filter(|cost| cost.amount > 0)
+            .map(|cost| QuoteLineItem {
+                description: cost.description,
+                amount: cost.amount as f64 / 100.0,
+            })
+            .collect();
+        fees.push(QuoteLineItem {
+            description: "Real Estate Commission".to_string(),
+            amount: Self::calculate_commission(discounted_price as i64, 3.0) as f64,
+        });
+
+        let mut taxes = vec![QuoteLineItem {
+            description: "Transfer Tax".to_string(),
+            amount: Self::calculate_transfer_tax(discounted_price as i64) as f64,
+        }];
+
+        let rate = self.currency.convert(1.0, None, Some(&currency))
+            .ok_or_else(|| QuoteError::InvalidInput(format!("Unsupported currency: {}", currency)))?;
+        for item in fees.iter_mut().chain(taxes.iter_mut()) {
+            item.amount *= rate;
+        }
+        let (base_price, discount_amount, discounted_price) =
+            (base_price * rate, discount_amount * rate, discounted_price * rate);
+
+        let total = discounted_price
+            + fees.iter().map(|fee| fee.amount).sum::<f64>()
+            + taxes.iter().map(|tax| tax.amount).sum::<f64>();
+
+        Ok(Quote {
+            property,
+            currency,
+            base_price,
+            discount_percentage,
+            discount_amount,
+            fees,
+            taxes,
+            total,
+            created_at: chrono::Utc::now(),
+            expires_at: chrono::Utc::now() + chrono::Duration::days(30),
+        })
+    }
+
+    pub async fn affordability(
+        &self,
+        contact_id: i32,
+        property_id: i32,
+        options: AffordabilityOptions,
+    ) -> Result<AffordabilityReport> {
+        let interest_rate = options.interest_rate.unwrap_or(6.5);
+        let loan_term_years = options.term_years.unwrap_or(30);
+        let down_payment_percentage = options.down_payment_percentage.unwrap_or(20.0);
+
+        if interest_rate < 0.0 {
+            return Err(QuoteError::InvalidInput("Interest rate must be non-negative".to_string()).into());
+        }
+        if loan_term_years <= 0 {
+            return Err(QuoteError::InvalidInput("Loan term must be at least one year".to_string()).into());
+        }
+        if !(0.0..=100.0).contains(&down_payment_percentage) {
+            return Err(QuoteError::InvalidInput("Down payment percentage must be between 0 and 100".to_string()).into());
+        }
+
+        let property = self.repository.get_property_by_id(property_id).await?
+            .ok_or(QuoteError::NotFound("Property"))?;
+
+        let contact = self.repository.get_contact_by_id(contact_id).await?
+            .ok_or(QuoteError::NotFound("Contact"))?;
+
+        // The report is in the contact's budget currency so the comparison is like for like
+        let currency = contact.currency.as_deref()
+            .map(str::to_uppercase)
+            .unwrap_or_else(|| self.currency.base().to_string());
+        if !self.currency.supports(&currency) {
+            return Err(anyhow::anyhow!("No exchange rate for contact {} currency {}", contact_id, currency));
+        }
+        let property_price = self.convert_price(&property, Some(&currency))?;
+
+        let down_payment = property_price * down_payment_percentage / 100.0;
+        let loan_amount = property_price - down_payment;
+        let financing = self.calculate_financing_option("Custom", loan_amount, interest_rate, loan_term_years);
+
+        let budget_position = if property_price < contact.min_budget {
+            "below_range"
+        } else if property_price <= contact.max_budget {
+            "within_range"
+        } else {
+            "above_range"
+        };
+
+        Ok(AffordabilityReport {
+            contact_id,
+            property_id,
+            currency,
+            property_price,
+            budget_min: contact.min_budget,
+            budget_max: contact.max_budget,
+            is_affordable: property_price <= contact.max_budget,
+            budget_headroom: contact.max_budget - property_price,
+            budget_position: budget_position.to_string(),
+            down_payment,
+            loan_amount,
+            interest_rate,
+            loan_term_years,
+            estimated_monthly_payment: financing.monthly_payment,
+            total_interest: financing.total_interest,
+        })
+    }
+
     pub async fn generate_comparison_quote(&self, request: ComparisonQuoteRequest) -> Result<ComparisonQuoteResponse> {
         // Get properties and contact
         let property1 = self.repository.get_property_by_id(request.property1_id).await?
@@ -335,10 +553,12 @@ impl QuoteService {
         };
 
         // Analyze property features
-        if property.number_of_rooms >= contact.min_rooms {
+        if contact.wants_rooms(property.number_of_rooms) {
             key_highlights.push(format!("Property has {} rooms, meeting your requirement", property.number_of_rooms));
-        } else {
+        } else if property.number_of_rooms < contact.min_rooms {
             potential_concerns.push("Property has fewer rooms than requested".to_string());
+        } else {
+            potential_concerns.push("Property has more rooms than requested".to_string());
         }
 
         // Property type check
@@ -457,3 +677,37 @@ impl QuoteService {
         (property_value as f64 * commission_percentage / 100.0) as i64
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    #[tokio::test]
+    async fn quote_currency_prefers_request_then_listing_then_base() {
+        let service = QuoteService::new(test_support::repository(), test_support::currency_converter());
+        let mut property = test_support::property(1, 1_000.0);
+
+        assert_eq!(service.quote_currency(None, &property).unwrap(), "DZD");
+        property.currency = Some("eur".to_string());
+        assert_eq!(service.quote_currency(None, &property).unwrap(), "EUR");
+        assert_eq!(service.quote_currency(Some("dzd"), &property).unwrap(), "DZD");
+        assert!(matches!(
+            service.quote_currency(Some("XYZ"), &property),
+            Err(QuoteError::InvalidInput(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn listing_prices_are_converted_or_rejected() {
+        let service = QuoteService::new(test_support::repository(), test_support::currency_converter());
+        let mut property = test_support::property(1, 1_000.0);
+        property.currency = Some("EUR".to_string());
+
+        assert_eq!(service.convert_price(&property, None).unwrap(), 150_000.0);
+        assert_eq!(service.convert_price(&property, Some("EUR")).unwrap(), 1_000.0);
+
+        property.currency = Some("XYZ".to_string());
+        assert!(service.convert_price(&property, None).is_err());
+    }
+}
@@ -1,32 +1,201 @@
+use crate::config::{DedupConfig, FeatureFlags, FeatureFlagsUpdate, FreshnessConfig, LocationFalloffConfig, PropertyTypeTaxonomy, RecommendationConfig, ScoringWeights};
 use crate::db::Repository;
+use crate::metrics;
+use crate::middleware::{current_request_id, is_dry_run, with_dry_run, with_request_id};
 use crate::models::*;
+use crate::utils::currency::CurrencyConverter;
 use crate::utils::scoring::*;
 use anyhow::Result;
 use chrono::Utc;
-use std::sync::Arc;
+use futures_util::stream::{BoxStream, StreamExt, TryStreamExt};
+use std::sync::{Arc, RwLock};
 use rayon::prelude::*;
 use moka::future::Cache;
+use std::collections::HashMap;
 use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Maximum number of contacts accepted by a single batch request.
+pub const MAX_CONTACTS_PER_BATCH: usize = 100;
+
+/// Number of contacts scored concurrently within a batch request.
+const BATCH_CONCURRENCY: usize = 8;
+
+/// Reported when a contact's best candidate falls below the quality floor.
+pub const NO_STRONG_MATCHES: &str = "no_strong_matches";
+
+/// Reported when contacts are requested for a listing that is no longer available.
+pub const PROPERTY_UNAVAILABLE: &str = "property_unavailable";
+
+#[derive(Clone)]
+struct CachedRecommendations {
+    recommendations: Vec<Recommendation>,
+    reason: Option<String>,
+}
+
+/// Options of a contact recommendation request, shared by the single-contact
+/// and batch paths so both rank and cache identically.
+#[derive(Debug, Clone)]
+struct ContactRequestOptions {
+    limit: Option<usize>,
+    min_score: Option<f64>,
+    top_k: Option<usize>,
+    top_percentile: Option<f64>,
+    score_threshold_percentile: Option<f64>,
+    max_distance_km: Option<f64>,
+    excluded_property_types: Option<Vec<String>>,
+    include_unavailable: bool,
+    sort_by: RecommendationSort,
+    weights: ScoringWeights,
+    flags: FeatureFlags, // Snapshot taken once per request, so a concurrent update cannot mix flag sets
+}
+
+impl ContactRequestOptions {
+    fn cache_key(&self, contact_id: i32) -> String {
+        format!(
+            "contact_{}_{:?}_{:?}_{:?}_{:?}_{:?}_{:?}_{:?}_{}_{:?}_{:.3}_{:.3}_{:.3}_{:.3}_{:?}", 
+            contact_id, self.limit, self.min_score, self.top_k, self.top_percentile, self.score_threshold_percentile,
+            self.max_distance_km, self.excluded_property_types, self.include_unavailable, self.sort_by,
+            self.weights.budget, self.weights.location, self.weights.property_type, self.weights.size,
+            self.flags
+        )
+    }
+}
+
+/// Whether a property lies within `radius_km` of one of the contact's preferred
+/// locations, each narrowed by that location's own radius. Mirrors the
+/// database radius search for listings already in memory.
+fn within_preferred_radius(contact: &Contact, property: &Property, radius_km: f64) -> bool {
+    contact.preferred_locations.iter().any(|location| {
+        let radius_km = location.radius_km.map_or(radius_km, |own| own.min(radius_km));
+        calculate_distance_km(location.lat, location.lon, property.location.lat, property.location.lon) <= radius_km
+    })
+}
 
 #[derive(Clone)]
 pub struct RecommendationService {
     repository: Arc<Repository>,
-    cache: Cache<String, Vec<Recommendation>>,
+    cache: Cache<String, CachedRecommendations>,
+    default_weights: ScoringWeights,
+    min_quality_threshold: f64,
+    dedup: DedupConfig,
+    freshness: FreshnessConfig,
+    property_types: Arc<PropertyTypeTaxonomy>,
+    location_falloff: LocationFalloffConfig,
+    currency: Arc<CurrencyConverter>,
+    feature_flags: Arc<RwLock<FeatureFlags>>,
+    slow_request_threshold_ms: u64,
 }
 
 impl RecommendationService {
-    pub fn new(repository: Arc<Repository>, cache_ttl: Duration, cache_capacity: u64) -> Self {
+    pub fn new(
+        repository: Arc<Repository>,
+        config: &RecommendationConfig,
+        cache_capacity: u64,
+        currency: CurrencyConverter,
+        feature_flags: FeatureFlags,
+    ) -> Self {
         let cache = Cache::builder()
-            .time_to_live(cache_ttl)
+            .time_to_live(Duration::from_secs(config.cache_ttl_seconds))
             .max_capacity(cache_capacity)
             .build();
 
         Self {
             repository,
             cache,
+            default_weights: config.scoring_weights,
+            min_quality_threshold: config.min_quality_threshold,
+            dedup: config.dedup,
+            freshness: config.freshness,
+            property_types: Arc::new(config.property_types.clone()),
+            location_falloff: config.location_falloff,
+            currency: Arc::new(currency),
+            feature_flags: Arc::new(RwLock::new(feature_flags)),
+            slow_request_threshold_ms: config.slow_request_threshold_ms,
         }
     }
 
+    pub fn feature_flags(&self) -> FeatureFlags {
+        *self.feature_flags.read().unwrap_or_else(|poisoned| {
+            // Flags are plain Copy values, so a panicking writer cannot leave them half-updated
+            log::error!("Feature flags lock was poisoned by a panic; recovering");
+            self.feature_flags.clear_poison();
+            poisoned.into_inner()
+        })
+    }
+
+    /// Applies a partial flag update for every worker and drops cached results
+    /// computed under the previous flags.
+    pub fn update_feature_flags(&self, update: &FeatureFlagsUpdate) -> FeatureFlags {
+        let flags = {
+            let mut flags = self.feature_flags.write().unwrap_or_else(|poisoned| {
+                log::error!("Feature flags lock was poisoned by a panic; recovering");
+                self.feature_flags.clear_poison();
+                poisoned.into_inner()
+            });
+            *flags = flags.with_update(update);
+            *flags
+        };
+        self.cache.invalidate_all();
+        log::info!("Feature flags updated: {:?}", flags);
+        flags
+    }
+
+    pub fn currency(&self) -> &CurrencyConverter {
+        &self.currency
+    }
+
+    pub fn default_weights(&self) -> ScoringWeights {
+        self.default_weights
+    }
+
+    /// Drops every cached response, e.g. after a listing changes status.
+    pub fn invalidate_cache(&self) {
+        self.cache.invalidate_all();
+    }
+
+    /// Why a pair is kept out of rankings: the contact's own exclusions, or a
+    /// price or budget in a currency without a configured rate, which could
+    /// only be compared unconverted.
+    fn exclusion(&self, contact: &Contact, property: &Property) -> Option<String> {
+        exclusion_reason(property, contact, &self.property_types).or_else(|| {
+            [property.currency.as_deref(), contact.currency.as_deref()]
+                .into_iter()
+                .flatten()
+                .find(|code| !self.currency.supports(code))
+                .map(|code| format!("No exchange rate for {}", code.to_uppercase()))
+        })
+    }
+
+    fn is_excluded(&self, contact: &Contact, property: &Property) -> bool {
+        self.exclusion(contact, property).is_some()
+    }
+
+    /// Logs the stage timings of a request: at info when it exceeded the slow
+    /// request threshold, at debug otherwise.
+    fn log_timing(&self, kind: &str, id: i32, candidates: usize, fetch_ms: u128, scoring_ms: u128, total_ms: u64) {
+        let slow = self.slow_request_threshold_ms > 0 && total_ms >= self.slow_request_threshold_ms;
+        let level = if slow { log::Level::Info } else { log::Level::Debug };
+        log::log!(
+            level,
+            "{}{} {} recommendations: {} candidates, fetch {}ms, scoring {}ms, post-processing {}ms, total {}ms",
+            if slow { "Slow request: " } else { "" },
+            kind,
+            id,
+            candidates,
+            fetch_ms,
+            scoring_ms,
+            (total_ms as u128).saturating_sub(fetch_ms + scoring_ms),
+            total_ms
+        );
+    }
+
+    fn quality_floor(&self, flags: &FeatureFlags) -> Option<f64> {
+        let enabled = flags.quality_floor && self.min_quality_threshold > 0.0;
+        enabled.then_some(self.min_quality_threshold)
+    }
+
     pub async fn get_recommendations_for_property(
         &self,
         property_id: i32,
@@ -35,10 +204,7 @@ impl RecommendationService {
         top_k: Option<usize>,
         top_percentile: Option<f64>,
         score_threshold_percentile: Option<f64>,
-        budget_weight: f64,
-        location_weight: f64,
-        property_type_weight: f64,
-        size_weight: f64,
+        weights: ScoringWeights,
     ) -> Result<RecommendationResponse> {
         let start_time = std::time::Instant::now();
         
@@ -46,40 +212,34 @@ impl RecommendationService {
         let cache_key = format!(
             "property_{}_{:?}_{:?}_{:?}_{:?}_{:?}_{:.3}_{:.3}_{:.3}_{:.3}", 
             property_id, limit, min_score, top_k, top_percentile, score_threshold_percentile,
-            budget_weight, location_weight, property_type_weight, size_weight
+            weights.budget, weights.location, weights.property_type, weights.size
         );
-        if let Some(cached_recommendations) = self.cache.get(&cache_key).await {
+        if let Some(cached) = self.cache.get(&cache_key).await {
+            metrics::record_cache_lookup(true);
+            metrics::observe_recommendation("property", start_time.elapsed());
             return Ok(RecommendationResponse {
-                recommendations: cached_recommendations.clone(),
-                total_count: cached_recommendations.len(),
+                total_count: cached.recommendations.len(),
+                recommendations: cached.recommendations,
                 processing_time_ms: start_time.elapsed().as_millis() as u64,
+                min_quality_threshold: None,
+                reason: cached.reason,
             });
         }
+        metrics::record_cache_lookup(false);
 
         // Get property and contacts
         let property = self.repository.get_property_by_id(property_id).await?
             .ok_or_else(|| anyhow::anyhow!("Property not found"))?;
         
         let contacts = self.repository.get_all_active_contacts().await?;
+        let fetch_ms = start_time.elapsed().as_millis();
 
-        // Calculate recommendations in parallel
-        let mut all_recommendations: Vec<Recommendation> = contacts
-            .par_iter()
-            .map(|contact| self.calculate_recommendation(
-                contact, 
-                &property, 
-                budget_weight, 
-                location_weight, 
-                property_type_weight, 
-                size_weight
-            ))
-            .collect();
-
-        // Sort by score (highest first) first for percentile calculations
-        all_recommendations.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        let flags = self.feature_flags();
+        let (all_recommendations, reason) = self.rank_for_property(&property, &contacts, &weights, &flags);
+        let scoring_ms = start_time.elapsed().as_millis() - fetch_ms;
 
         // Apply advanced filtering
-        let filtered_recommendations = self.apply_advanced_filters(
+        let final_recommendations = self.apply_advanced_filters(
             all_recommendations,
             min_score,
             top_k,
@@ -88,20 +248,52 @@ impl RecommendationService {
             limit,
         );
 
-        let final_recommendations = filtered_recommendations;
-
-        // Cache the results
-        self.cache.insert(cache_key, final_recommendations.clone()).await;
+        // Cache the results, unless this is a dry run
+        if !is_dry_run() {
+            self.cache.insert(cache_key, CachedRecommendations {
+                recommendations: final_recommendations.clone(),
+                reason: reason.clone(),
+            }).await;
+        }
 
+        metrics::observe_recommendation("property", start_time.elapsed());
         let processing_time = start_time.elapsed().as_millis() as u64;
+        self.log_timing("Property", property_id, contacts.len(), fetch_ms, scoring_ms, processing_time);
 
         Ok(RecommendationResponse {
             total_count: final_recommendations.len(),
             recommendations: final_recommendations,
             processing_time_ms: processing_time,
+            min_quality_threshold: None,
+            reason,
         })
     }
 
+    /// Scores and sorts contacts for a property. Sold, rented or under-offer
+    /// listings have no one to recommend them to, so nothing is scored.
+    fn rank_for_property(
+        &self,
+        property: &Property,
+        contacts: &[Contact],
+        weights: &ScoringWeights,
+        flags: &FeatureFlags,
+    ) -> (Vec<Recommendation>, Option<String>) {
+        if property.availability != PropertyAvailability::Available {
+            return (Vec::new(), Some(PROPERTY_UNAVAILABLE.to_string()));
+        }
+
+        // Calculate recommendations in parallel
+        let mut all_recommendations: Vec<Recommendation> = contacts
+            .par_iter()
+            .filter(|contact| !self.is_excluded(contact, property))
+            .map(|contact| self.calculate_recommendation(contact, property, weights, flags))
+            .collect();
+
+        // Sort by score (highest first) first for percentile calculations
+        sort_recommendations(&mut all_recommendations);
+        (all_recommendations, None)
+    }
+
     pub async fn get_recommendations_for_contact(
         &self,
         contact_id: i32,
@@ -110,72 +302,169 @@ impl RecommendationService {
         top_k: Option<usize>,
         top_percentile: Option<f64>,
         score_threshold_percentile: Option<f64>,
-        budget_weight: f64,
-        location_weight: f64,
-        property_type_weight: f64,
-        size_weight: f64,
+        max_distance_km: Option<f64>,
+        excluded_property_types: Option<Vec<String>>,
+        include_unavailable: bool,
+        sort_by: RecommendationSort,
+        weights: ScoringWeights,
+    ) -> Result<RecommendationResponse> {
+        let options = ContactRequestOptions {
+            limit,
+            min_score,
+            top_k,
+            top_percentile,
+            score_threshold_percentile,
+            max_distance_km,
+            excluded_property_types,
+            include_unavailable,
+            sort_by,
+            weights,
+            flags: self.feature_flags(),
+        };
+        self.recommend_for_contact(contact_id, options, None).await
+    }
+
+    /// Ranks properties for one contact. Batch requests pass the listings they
+    /// loaded once in `shared_properties`; otherwise candidates are fetched
+    /// here, with the database doing any radius search.
+    async fn recommend_for_contact(
+        &self,
+        contact_id: i32,
+        options: ContactRequestOptions,
+        shared_properties: Option<Arc<Vec<Property>>>,
     ) -> Result<RecommendationResponse> {
         let start_time = std::time::Instant::now();
         
         // Check cache first
-        let cache_key = format!(
-            "contact_{}_{:?}_{:?}_{:?}_{:?}_{:?}_{:.3}_{:.3}_{:.3}_{:.3}", 
-            contact_id, limit, min_score, top_k, top_percentile, score_threshold_percentile,
-            budget_weight, location_weight, property_type_weight, size_weight
-        );
-        if let Some(cached_recommendations) = self.cache.get(&cache_key).await {
+        let cache_key = options.cache_key(contact_id);
+        if let Some(cached) = self.cache.get(&cache_key).await {
+            metrics::record_cache_lookup(true);
+            metrics::observe_recommendation("contact", start_time.elapsed());
             return Ok(RecommendationResponse {
-                recommendations: cached_recommendations.clone(),
-                total_count: cached_recommendations.len(),
+                total_count: cached.recommendations.len(),
+                recommendations: cached.recommendations,
                 processing_time_ms: start_time.elapsed().as_millis() as u64,
+                min_quality_threshold: self.quality_floor(&options.flags),
+                reason: cached.reason,
             });
         }
+        metrics::record_cache_lookup(false);
 
         // Get contact and properties
-        let contact = self.repository.get_contact_by_id(contact_id).await?
+        let mut contact = self.repository.get_contact_by_id(contact_id).await?
             .ok_or_else(|| anyhow::anyhow!("Contact not found"))?;
+        if let Some(excluded_property_types) = &options.excluded_property_types {
+            contact.excluded_property_types = excluded_property_types.clone();
+        }
         
-        let properties = self.repository.get_all_active_properties().await?;
-
-        // Calculate recommendations in parallel
-        let mut all_recommendations: Vec<Recommendation> = properties
-            .par_iter()
-            .map(|property| self.calculate_recommendation(
-                &contact, 
-                property, 
-                budget_weight, 
-                location_weight, 
-                property_type_weight, 
-                size_weight
-            ))
-            .collect();
+        let location_radius_km = options.max_distance_km.filter(|_| !contact.preferred_locations.is_empty());
+        let (properties, radius_filter_km) = match (shared_properties, location_radius_km) {
+            // Shared listings are unfiltered, so the radius is checked in memory
+            (Some(properties), radius_km) => (properties, radius_km),
+            // Location-constrained requests let the database do the radius search
+            (None, Some(radius_km)) => {
+                let mut nearby: HashMap<i32, Property> = HashMap::new();
+                for location in &contact.preferred_locations {
+                    // A preference's own radius can only narrow the requested one
+                    let radius_km = location.radius_km.map_or(radius_km, |own| own.min(radius_km));
+                    let properties = self.repository
+                        .find_properties_near(location.lat, location.lon, radius_km, None)
+                        .await?;
+                    for property in properties {
+                        nearby.entry(property.id).or_insert(property);
+                    }
+                }
+                (Arc::new(nearby.into_values().collect()), None)
+            }
+            (None, None) => (Arc::new(self.repository.get_all_active_properties().await?), None),
+        };
+        let candidates = properties.len();
+        let fetch_ms = start_time.elapsed().as_millis();
 
-        // Sort by score (highest first) first for percentile calculations
-        all_recommendations.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        // Scoring is CPU-bound, so it runs off the async workers
+        let flags = options.flags;
+        let service = self.clone();
+        let (final_recommendations, reason) = tokio::task::spawn_blocking(move || {
+            service.rank_for_contact(&contact, &properties, radius_filter_km, &options)
+        }).await?;
+        let scoring_ms = start_time.elapsed().as_millis() - fetch_ms;
 
-        // Apply advanced filtering
-        let final_recommendations = self.apply_advanced_filters(
-            all_recommendations,
-            min_score,
-            top_k,
-            top_percentile,
-            score_threshold_percentile,
-            limit,
-        );
-
-        // Cache the results
-        self.cache.insert(cache_key, final_recommendations.clone()).await;
+        // Cache the results, unless this is a dry run
+        if !is_dry_run() {
+            self.cache.insert(cache_key, CachedRecommendations {
+                recommendations: final_recommendations.clone(),
+                reason: reason.clone(),
+            }).await;
+        }
 
+        metrics::observe_recommendation("contact", start_time.elapsed());
         let processing_time = start_time.elapsed().as_millis() as u64;
+        self.log_timing("Contact", contact_id, candidates, fetch_ms, scoring_ms, processing_time);
 
         Ok(RecommendationResponse {
             total_count: final_recommendations.len(),
             recommendations: final_recommendations,
             // recommendations: sorted_recommendations,
             processing_time_ms: processing_time,
+            min_quality_threshold: self.quality_floor(&flags),
+            reason,
         })
     }
 
+    /// Scores, filters and ranks candidate properties for a contact. When
+    /// `radius_filter_km` is set, only properties within it of one of the
+    /// contact's preferred locations are considered.
+    fn rank_for_contact(
+        &self,
+        contact: &Contact,
+        properties: &[Property],
+        radius_filter_km: Option<f64>,
+        options: &ContactRequestOptions,
+    ) -> (Vec<Recommendation>, Option<String>) {
+        // Calculate recommendations in parallel
+        // Exclusions and sold/rented listings are hard filters, applied before any scoring work
+        let mut all_recommendations: Vec<Recommendation> = properties
+            .par_iter()
+            .filter(|property| options.include_unavailable || property.availability == PropertyAvailability::Available)
+            .filter(|property| radius_filter_km.is_none_or(|radius_km| within_preferred_radius(contact, property, radius_km)))
+            .filter(|property| !self.is_excluded(contact, property))
+            .map(|property| self.calculate_recommendation(contact, property, &options.weights, &options.flags))
+            .collect();
+
+        // Sort by score (highest first) first for percentile calculations
+        sort_recommendations(&mut all_recommendations);
+
+        // Rather than padding with weak candidates, return nothing when even the
+        // best match misses the quality floor
+        let best_score = all_recommendations.first().map(|r| r.score);
+        match (self.quality_floor(&options.flags), best_score) {
+            (Some(floor), Some(best)) if best.is_nan() || best < floor => {
+                (Vec::new(), Some(NO_STRONG_MATCHES.to_string()))
+            }
+            _ => {
+                // Re-listed units would otherwise take several result slots
+                let unique_recommendations = if options.flags.deduplication {
+                    dedup_recommendations(all_recommendations, &self.dedup, &self.currency)
+                } else {
+                    all_recommendations
+                };
+                // Score cutoffs choose the candidates; the requested order then
+                // decides which of them the count caps keep
+                let mut selected = self.apply_advanced_filters(
+                    unique_recommendations,
+                    options.min_score,
+                    None,
+                    options.top_percentile,
+                    options.score_threshold_percentile,
+                    None,
+                );
+                sort_recommendations_by(&mut selected, options.sort_by);
+                let capped = self.apply_advanced_filters(selected, None, options.top_k, None, None, options.limit);
+                (capped, None)
+            }
+        }
+    }
+
     pub async fn get_bulk_recommendations(
         &self,
         request: BulkRecommendationRequest,
@@ -183,7 +472,8 @@ impl RecommendationService {
         let start_time = std::time::Instant::now();
 
         // Get weights from request
-        let (budget_weight, location_weight, property_type_weight, size_weight) = request.get_weights();
+        let weights = request.resolve_weights(self.default_weights)
+            .map_err(anyhow::Error::msg)?;
 
         // Get properties (either specified ones or all active)
         let properties = if let Some(property_ids) = &request.property_ids {
@@ -195,10 +485,15 @@ impl RecommendationService {
             }
             result
         } else {
+            // Only explicitly requested properties are scored when no longer available
             self.repository.get_all_active_properties().await?
+                .into_iter()
+                .filter(|property| request.include_unavailable || property.availability == PropertyAvailability::Available)
+                .collect()
         };
 
         let contacts = self.repository.get_all_active_contacts().await?;
+        let flags = self.feature_flags();
 
         // Process in parallel
         let property_recommendations: Vec<PropertyRecommendations> = properties
@@ -206,18 +501,12 @@ impl RecommendationService {
             .map(|property| {
                 let mut all_recommendations: Vec<Recommendation> = contacts
                     .par_iter()
-                    .map(|contact| self.calculate_recommendation(
-                        contact, 
-                        property, 
-                        budget_weight, 
-                        location_weight, 
-                        property_type_weight, 
-                        size_weight
-                    ))
+                    .filter(|contact| !self.is_excluded(contact, property))
+                    .map(|contact| self.calculate_recommendation(contact, property, &weights, &flags))
                     .collect();
 
                 // Sort by score (highest first) first for percentile calculations
-                all_recommendations.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+                sort_recommendations(&mut all_recommendations);
 
                 // Apply advanced filtering
                 let filtered_recommendations = self.apply_advanced_filters(
@@ -243,6 +532,7 @@ impl RecommendationService {
             .map(|pr| pr.recommendation_count)
             .sum();
 
+        metrics::observe_recommendation("bulk", start_time.elapsed());
         let processing_time = start_time.elapsed().as_millis() as u64;
 
         Ok(BulkRecommendationResponse {
@@ -253,6 +543,177 @@ impl RecommendationService {
         })
     }
 
+    pub async fn get_recommendations_for_contacts(
+        &self,
+        contact_ids: &[i32],
+        request: &BatchContactRecommendationRequest,
+    ) -> Result<BatchContactRecommendationResponse> {
+        let start_time = std::time::Instant::now();
+
+        if contact_ids.len() > MAX_CONTACTS_PER_BATCH {
+            return Err(anyhow::anyhow!(
+                "At most {} contacts can be requested at once, got {}",
+                MAX_CONTACTS_PER_BATCH,
+                contact_ids.len()
+            ));
+        }
+
+        let weights = request.resolve_weights(self.default_weights)
+            .map_err(anyhow::Error::msg)?;
+
+        // Duplicate ids would only repeat work, so each contact is scored once
+        let mut unique_ids = contact_ids.to_vec();
+        unique_ids.sort_unstable();
+        unique_ids.dedup();
+
+        let options = ContactRequestOptions {
+            limit: request.limit,
+            min_score: request.min_score,
+            top_k: request.top_k,
+            top_percentile: request.top_percentile,
+            score_threshold_percentile: request.score_threshold_percentile,
+            max_distance_km: request.max_distance_km,
+            excluded_property_types: request.excluded_property_types.clone(),
+            include_unavailable: request.include_unavailable,
+            sort_by: request.sort_by,
+            weights,
+            flags: self.feature_flags(),
+        };
+
+        // Every contact is ranked against the same listings, so they are loaded once
+        let properties = Arc::new(self.repository.get_all_active_properties().await?);
+
+        let semaphore = Arc::new(Semaphore::new(BATCH_CONCURRENCY));
+        let mut tasks = JoinSet::new();
+
+        for contact_id in unique_ids.iter().copied() {
+            let permit = semaphore.clone().acquire_owned().await?;
+            let service = self.clone();
+            let options = options.clone();
+            let properties = properties.clone();
+
+            let request_id = current_request_id();
+            let dry_run = is_dry_run();
+
+            tasks.spawn(with_request_id(request_id, with_dry_run(dry_run, async move {
+                let result = service.recommend_for_contact(contact_id, options, Some(properties)).await;
+                drop(permit);
+                (contact_id, result)
+            })));
+        }
+
+        let mut results = HashMap::new();
+        let mut errors = HashMap::new();
+
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((contact_id, Ok(response))) => {
+                    results.insert(contact_id, response);
+                }
+                Ok((contact_id, Err(e))) => {
+                    errors.insert(contact_id, e.to_string());
+                }
+                Err(e) => {
+                    log::error!("Batch recommendation task failed: {}", e);
+                }
+            }
+        }
+
+        // A panicked task loses its id, so report any contact that produced no outcome
+        for contact_id in unique_ids {
+            if !results.contains_key(&contact_id) && !errors.contains_key(&contact_id) {
+                errors.insert(contact_id, "Recommendation task failed".to_string());
+            }
+        }
+
+        metrics::observe_recommendation("batch", start_time.elapsed());
+        let processing_time = start_time.elapsed().as_millis() as u64;
+
+        Ok(BatchContactRecommendationResponse {
+            total_contacts: results.len() + errors.len(),
+            successful_contacts: results.len(),
+            failed_contacts: errors.len(),
+            results,
+            errors,
+            processing_time_ms: processing_time,
+        })
+    }
+
+    /// Lazily scores every property for a contact, yielding recommendations in
+    /// property id order as rows arrive. Results are not ranked; `min_score`,
+    /// `max_distance_km` and exclusions are applied per item. Re-listed units
+    /// are not collapsed, since that needs the whole ranking before the first
+    /// item could be sent. Returns `None` when the contact does not exist.
+    pub async fn stream_recommendations_for_contact(
+        &self,
+        contact_id: i32,
+        min_score: Option<f64>,
+        max_distance_km: Option<f64>,
+        excluded_property_types: Option<Vec<String>>,
+        include_unavailable: bool,
+        weights: ScoringWeights,
+    ) -> Result<Option<BoxStream<'static, Result<Recommendation>>>> {
+        let mut contact = match self.repository.get_contact_by_id(contact_id).await? {
+            Some(contact) => contact,
+            None => return Ok(None),
+        };
+        if let Some(excluded_property_types) = excluded_property_types {
+            contact.excluded_property_types = excluded_property_types;
+        }
+        // As in ranked requests, a radius only applies to contacts with preferred locations
+        let radius_filter_km = max_distance_km.filter(|_| !contact.preferred_locations.is_empty());
+
+        let flags = self.feature_flags();
+        let service = self.clone();
+        let stream = self.repository
+            .stream_all_properties()
+            .try_filter({
+                let service = self.clone();
+                let contact = contact.clone();
+                move |property| {
+                    let available = include_unavailable || property.availability == PropertyAvailability::Available;
+                    let nearby = radius_filter_km.is_none_or(|radius_km| within_preferred_radius(&contact, property, radius_km));
+                    futures_util::future::ready(available && nearby && !service.is_excluded(&contact, property))
+                }
+            })
+            .map_ok(move |property| service.calculate_recommendation(&contact, &property, &weights, &flags))
+            .try_filter(move |recommendation| {
+                let keep = min_score.is_none_or(|min| recommendation.score >= min);
+                futures_util::future::ready(keep)
+            })
+            .boxed();
+
+        Ok(Some(stream))
+    }
+
+    /// Scores a single contact/property pair, regardless of where the property
+    /// would rank. Returns `None` when either id is unknown.
+    pub async fn explain(
+        &self,
+        contact_id: i32,
+        property_id: i32,
+        weights: ScoringWeights,
+    ) -> Result<Option<RecommendationExplanation>> {
+        let contact = match self.repository.get_contact_by_id(contact_id).await? {
+            Some(contact) => contact,
+            None => return Ok(None),
+        };
+        let property = match self.repository.get_property_by_id(property_id).await? {
+            Some(property) => property,
+            None => return Ok(None),
+        };
+
+        Ok(Some(self.calculate_recommendation(&contact, &property, &weights, &self.feature_flags()).explanation))
+    }
+
+    /// Narrows score-sorted recommendations. Filters apply in a fixed order:
+    ///
+    /// 1. `score_threshold_percentile` and `min_score` are absolute cutoffs. The
+    ///    percentile cutoff is the score at that percentile of *all* candidates,
+    ///    so it does not depend on `min_score`; when both are set the stricter
+    ///    one wins. Ties with the cutoff score are kept.
+    /// 2. `top_percentile` keeps that fraction (rounded up) of what survived.
+    /// 3. `top_k`, then `limit`, cap the count; the smaller of the two wins.
     fn apply_advanced_filters(
         &self,
         mut recommendations: Vec<Recommendation>,
@@ -262,36 +723,27 @@ impl RecommendationService {
         score_threshold_percentile: Option<f64>,
         limit: Option<usize>,
     ) -> Vec<Recommendation> {
-        // Step 1: Apply minimum score filter
-        if let Some(min_score) = min_score {
-            recommendations.retain(|r| r.score >= min_score);
-        }
-
-        // Step 2: Apply score threshold percentile filter
-        if let Some(percentile) = score_threshold_percentile {
-            if !recommendations.is_empty() {
-                let threshold_index = ((1.0 - percentile) * recommendations.len() as f64).floor() as usize;
-                if threshold_index < recommendations.len() {
-                    let threshold_score = recommendations[threshold_index].score;
-                    recommendations.retain(|r| r.score >= threshold_score);
-                }
-            }
+        // Step 1: Absolute cutoffs, taken from the full candidate distribution
+        let percentile_cutoff = score_threshold_percentile
+            .and_then(|percentile| percentile_cutoff(&recommendations, percentile));
+        let cutoff = match (min_score, percentile_cutoff) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        if let Some(cutoff) = cutoff {
+            recommendations.retain(|r| r.score >= cutoff);
         }
 
-        // Step 3: Apply top percentile filter
+        // Step 2: Relative cut on what is left
         if let Some(percentile) = top_percentile {
-            if !recommendations.is_empty() {
-                let keep_count = (percentile * recommendations.len() as f64).ceil() as usize;
-                recommendations.truncate(keep_count.min(recommendations.len()));
-            }
+            let keep_count = fraction_count(percentile, recommendations.len());
+            recommendations.truncate(keep_count);
         }
 
-        // Step 4: Apply top K filter
+        // Step 3: Count caps
         if let Some(k) = top_k {
             recommendations.truncate(k);
         }
-
-        // Step 5: Apply final limit (for backward compatibility)
         if let Some(limit) = limit {
             recommendations.truncate(limit);
         }
@@ -299,32 +751,49 @@ impl RecommendationService {
         recommendations
     }
 
-    fn calculate_recommendation(
+    /// Scores one contact/property pair with the configured taxonomy and
+    /// currency conversion. Callers pass the feature flags they snapshotted for
+    /// the request rather than reading the lock per candidate.
+    pub fn calculate_recommendation(
         &self, 
         contact: &Contact, 
         property: &Property,
-        budget_weight: f64,
-        location_weight: f64,
-        property_type_weight: f64,
-        size_weight: f64,
+        weights: &ScoringWeights,
+        flags: &FeatureFlags,
     ) -> Recommendation {
+        // Prices and budgets may be in different currencies; compare them in the base
+        // currency. Amounts without a rate are flagged in `excluded` and kept out of
+        // rankings, so their raw figure only fills in the explanation.
+        let price = self.currency.to_base(property.price, property.currency.as_deref()).unwrap_or(property.price);
+        let min_budget = self.currency.to_base(contact.min_budget, contact.currency.as_deref()).unwrap_or(contact.min_budget);
+        let max_budget = self.currency.to_base(contact.max_budget, contact.currency.as_deref()).unwrap_or(contact.max_budget);
+
         // Calculate individual scores
-        let budget_score = calculate_budget_score(property.price, contact.min_budget, contact.max_budget);
-        let location_score = calculate_location_score(property, contact);
-        let property_type_score = calculate_property_type_score(property, contact);
+        let budget_score = calculate_budget_score(price, min_budget, max_budget);
+        let location_score = calculate_location_score(property, contact, &self.location_falloff);
+        let property_type_score = calculate_property_type_score(property, contact, &self.property_types);
         let size_score = calculate_size_score(property, contact);
+        let amenity_score = calculate_amenity_score(property, contact);
+        let (matched_amenities, missing_amenities) = match_amenities(property, contact);
 
         // Calculate overall score with configurable weights
-        let overall_score = calculate_overall_score(
+        let weighted_score = calculate_overall_score(
             budget_score,
             location_score,
             property_type_score,
             size_score,
-            budget_weight,
-            location_weight,
-            property_type_weight,
-            size_weight,
+            weights,
         );
+        let relevance_score = apply_amenity_penalty(weighted_score, amenity_score);
+
+        // Blend in a decaying boost so new listings are not buried by established ones
+        let now = Utc::now();
+        let freshness_score = calculate_freshness_score(property.listed_at, now, &self.freshness);
+        let overall_score = if flags.freshness_boost {
+            apply_freshness_boost(relevance_score, freshness_score, &self.freshness)
+        } else {
+            relevance_score
+        };
 
         // Calculate closest distance to preferred locations
         let min_distance = if !contact.preferred_locations.is_empty() {
@@ -339,6 +808,10 @@ impl RecommendationService {
         } else {
             0.0
         };
+        let within_preferred_radius = contact.preferred_locations.iter().any(|loc| {
+            calculate_distance_km(property.location.lat, property.location.lon, loc.lat, loc.lon)
+                <= loc.radius_km.unwrap_or(DEFAULT_LOCATION_RADIUS_KM)
+        });
 
         // Generate explanation reasons
         let mut reasons = Vec::new();
@@ -361,6 +834,8 @@ impl RecommendationService {
 
         if property_type_score == 1.0 {
             reasons.push("Preferred property type".to_string());
+        } else if property_type_score > 0.0 && !contact.property_types.is_empty() {
+            reasons.push(format!("Related property type ({})", property.property_type));
         } else if property_type_score == 0.0 {
             reasons.push("Different property type".to_string());
         }
@@ -371,6 +846,21 @@ impl RecommendationService {
             reasons.push("Size concerns".to_string());
         }
 
+        if !missing_amenities.is_empty() {
+            reasons.push(format!("Missing required amenities: {}", missing_amenities.join(", ")));
+        } else if !matched_amenities.is_empty() {
+            reasons.push("Has all required amenities".to_string());
+        }
+
+        let excluded = self.exclusion(contact, property);
+        if let Some(exclusion) = &excluded {
+            reasons.push(exclusion.clone());
+        }
+
+        if flags.freshness_boost && self.freshness.weight > 0.0 && freshness_score > 0.5 {
+            reasons.push("Newly listed".to_string());
+        }
+
         if reasons.is_empty() {
             reasons.push("Meets basic criteria".to_string());
         }
@@ -382,9 +872,9 @@ impl RecommendationService {
             explanation: RecommendationExplanation {
                 overall_score,
                 budget_match: BudgetMatch {
-                    is_within_budget: property.price >= contact.min_budget && property.price <= contact.max_budget,
-                    budget_utilization: if contact.max_budget > contact.min_budget {
-                        (property.price - contact.min_budget) / (contact.max_budget - contact.min_budget)
+                    is_within_budget: price >= min_budget && price <= max_budget,
+                    budget_utilization: if max_budget > min_budget {
+                        (price - min_budget) / (max_budget - min_budget)
                     } else {
                         1.0
                     },
@@ -392,18 +882,240 @@ impl RecommendationService {
                 },
                 location_match: LocationMatch {
                     distance_km: min_distance,
-                    is_preferred_location: min_distance <= 15.0, // Within 15km is considered preferred
+                    is_preferred_location: within_preferred_radius,
                     score: location_score,
                 },
-                property_type_match: contact.property_types.contains(&property.property_type),
+                property_type_match: property_type_score >= 1.0,
+                property_type_score,
                 size_match: SizeMatch {
-                    rooms_match: property.number_of_rooms >= contact.min_rooms,
+                    rooms_match: contact.wants_rooms(property.number_of_rooms),
                     area_match: property.area_sqm >= contact.min_area_sqm && property.area_sqm <= contact.max_area_sqm,
                     score: size_score,
                 },
+                amenity_match: AmenityMatch {
+                    matched: matched_amenities,
+                    missing: missing_amenities,
+                    score: amenity_score,
+                },
+                // In the base currency, like the budget figures, so listings compare across markets
+                price_per_sqm: property.price_per_sqm().map(|_| price / property.area_sqm as f64),
+                excluded,
+                freshness: FreshnessBoost {
+                    days_listed: (now - property.listed_at).num_seconds().max(0) as f64 / 86_400.0,
+                    score: freshness_score,
+                    contribution: overall_score - relevance_score,
+                },
                 reasons,
             },
-            created_at: Utc::now(),
+            created_at: now,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, ORIGIN};
+
+    fn ranked_ids(
+        service: &RecommendationService,
+        contact: &Contact,
+        properties: &[Property],
+        weights: &ScoringWeights,
+    ) -> Vec<i32> {
+        let mut recommendations: Vec<Recommendation> = properties
+            .iter()
+            .map(|property| service.calculate_recommendation(contact, property, weights, &FeatureFlags::default()))
+            .collect();
+        sort_recommendations(&mut recommendations);
+        recommendations.iter().map(|r| r.property.id).collect()
+    }
+
+    #[tokio::test]
+    async fn weights_change_the_ranking() {
+        let service = test_support::recommendation_service(FeatureFlags::default());
+        let contact = test_support::contact(1);
+
+        // Well within budget but about 50km from the preferred location
+        let mut far_affordable = test_support::property(1, 170_000.0);
+        far_affordable.location.lat = ORIGIN.0 + 0.45;
+        // Next door but 30% over budget
+        let near_expensive = test_support::property(2, 260_000.0);
+        let properties = [far_affordable, near_expensive];
+
+        let budget_heavy = ScoringWeights { budget: 0.7, location: 0.1, property_type: 0.1, size: 0.1 }
+            .normalized()
+            .unwrap();
+        assert_eq!(ranked_ids(&service, &contact, &properties, &budget_heavy), vec![1, 2]);
+
+        let location_heavy = ScoringWeights { budget: 0.1, location: 0.7, property_type: 0.1, size: 0.1 }
+            .normalized()
+            .unwrap();
+        assert_eq!(ranked_ids(&service, &contact, &properties, &location_heavy), vec![2, 1]);
+    }
+
+    #[test]
+    fn in_memory_radius_filter_honours_each_locations_own_radius() {
+        let property = test_support::property(1, 150_000.0);
+        let mut contact = test_support::contact(1);
+        // About 11km north of the property
+        contact.preferred_locations = vec![test_support::location(ORIGIN.0 + 0.1, ORIGIN.1, None)];
+
+        assert!(within_preferred_radius(&contact, &property, 15.0));
+        assert!(!within_preferred_radius(&contact, &property, 10.0));
+
+        // A smaller radius on the location narrows the requested one
+        contact.preferred_locations[0].radius_km = Some(5.0);
+        assert!(!within_preferred_radius(&contact, &property, 15.0));
+    }
+
+    #[tokio::test]
+    async fn newly_listed_reason_follows_the_freshness_flag() {
+        let service = test_support::recommendation_service(FeatureFlags::default());
+        let contact = test_support::contact(1);
+        let mut property = test_support::property(1, 150_000.0);
+        property.listed_at = Utc::now();
+        let weights = ScoringWeights::default();
+
+        let boosted = service.calculate_recommendation(&contact, &property, &weights, &FeatureFlags::default());
+        assert!(boosted.explanation.reasons.iter().any(|reason| reason == "Newly listed"));
+
+        let flags = FeatureFlags { freshness_boost: false, ..FeatureFlags::default() };
+        let plain = service.calculate_recommendation(&contact, &property, &weights, &flags);
+        assert!(!plain.explanation.reasons.iter().any(|reason| reason == "Newly listed"));
+        assert_eq!(plain.explanation.freshness.contribution, 0.0);
+    }
+
+    #[tokio::test]
+    async fn amounts_without_an_exchange_rate_are_flagged_and_not_ranked() {
+        let service = test_support::recommendation_service(FeatureFlags::default());
+        let contact = test_support::contact(1);
+        let in_euros = Property { currency: Some("EUR".to_string()), ..test_support::property(1, 1_000.0) };
+        let in_pounds = Property { currency: Some("GBP".to_string()), ..test_support::property(2, 1_000.0) };
+        let weights = ScoringWeights::default();
+
+        let converted = service.calculate_recommendation(&contact, &in_euros, &weights, &FeatureFlags::default());
+        assert!(converted.explanation.budget_match.is_within_budget);
+        assert_eq!(converted.explanation.excluded, None);
+
+        let flagged = service.calculate_recommendation(&contact, &in_pounds, &weights, &FeatureFlags::default());
+        assert_eq!(flagged.explanation.excluded.as_deref(), Some("No exchange rate for GBP"));
+
+        let (ranked, _) = service.rank_for_contact(&contact, &[in_euros, in_pounds], None, &contact_options());
+        assert_eq!(ids(&ranked), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn unavailable_properties_get_no_contacts() {
+        let service = test_support::recommendation_service(FeatureFlags::default());
+        let contacts = [test_support::contact(1), test_support::contact(2)];
+        let mut property = test_support::property(1, 150_000.0);
+        let weights = ScoringWeights::default();
+
+        let (recommendations, reason) = service.rank_for_property(&property, &contacts, &weights, &FeatureFlags::default());
+        assert_eq!(recommendations.len(), 2);
+        assert_eq!(reason, None);
+
+        for availability in [PropertyAvailability::UnderOffer, PropertyAvailability::Sold, PropertyAvailability::Rented] {
+            property.availability = availability;
+            let (recommendations, reason) = service.rank_for_property(&property, &contacts, &weights, &FeatureFlags::default());
+            assert!(recommendations.is_empty());
+            assert_eq!(reason.as_deref(), Some(PROPERTY_UNAVAILABLE));
+        }
+    }
+
+    fn contact_options() -> ContactRequestOptions {
+        ContactRequestOptions {
+            limit: None,
+            min_score: Some(0.0),
+            top_k: None,
+            top_percentile: None,
+            score_threshold_percentile: None,
+            max_distance_km: None,
+            excluded_property_types: None,
+            include_unavailable: false,
+            sort_by: RecommendationSort::Score,
+            weights: ScoringWeights::default(),
+            flags: FeatureFlags::default(),
         }
     }
+
+    #[tokio::test]
+    async fn sort_order_is_applied_before_the_count_caps() {
+        let service = test_support::recommendation_service(FeatureFlags::default());
+        let contact = test_support::contact(1);
+        // 1500/m² and a perfect fit, against 750/m² but larger than the contact wants
+        let best_match = test_support::property(1, 150_000.0);
+        let mut cheapest_per_sqm = test_support::property(2, 150_000.0);
+        cheapest_per_sqm.area_sqm = 200;
+        let properties = [best_match, cheapest_per_sqm];
+
+        let options = ContactRequestOptions { top_k: Some(1), ..contact_options() };
+        let (by_score, _) = service.rank_for_contact(&contact, &properties, None, &options);
+        assert_eq!(ids(&by_score), vec![1]);
+
+        let options = ContactRequestOptions { sort_by: RecommendationSort::PricePerSqmAsc, ..options };
+        let (by_price, _) = service.rank_for_contact(&contact, &properties, None, &options);
+        assert_eq!(ids(&by_price), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn contact_update_reaches_the_next_recommendation_call() {
+        let service = test_support::recommendation_service(FeatureFlags::default());
+        let mut contact = test_support::contact(1);
+        // Half again over the 100k-200k budget
+        let properties = [test_support::property(1, 300_000.0)];
+        let options = contact_options();
+        let cache_key = options.cache_key(contact.id);
+
+        let (before, reason) = service.rank_for_contact(&contact, &properties, None, &options);
+        assert!(!before[0].explanation.budget_match.is_within_budget);
+        service.cache.insert(cache_key.clone(), CachedRecommendations { recommendations: before.clone(), reason }).await;
+
+        // What PATCH /contacts/{id} does once the row is written
+        let update: UpdateContactRequest =
+            serde_json::from_str(r#"{"preferences": {"max_budget": 350000}}"#).unwrap();
+        update.apply_to(&mut contact);
+        service.invalidate_cache();
+
+        // The next call misses the cache and scores against the new budget
+        assert!(service.cache.get(&cache_key).await.is_none());
+        let (after, _) = service.rank_for_contact(&contact, &properties, None, &options);
+        assert!(after[0].explanation.budget_match.is_within_budget);
+        assert!(after[0].score > before[0].score);
+    }
+
+    fn scored(count: usize) -> Vec<Recommendation> {
+        // Ids 1..=count with scores 0.9, 0.8, ... descending
+        (0..count)
+            .map(|index| {
+                let property = test_support::property(index as i32 + 1, 150_000.0);
+                test_support::recommendation(property, 0.9 - index as f64 / 10.0)
+            })
+            .collect()
+    }
+
+    fn ids(recommendations: &[Recommendation]) -> Vec<i32> {
+        recommendations.iter().map(|r| r.property.id).collect()
+    }
+
+    #[tokio::test]
+    async fn advanced_filters_apply_cutoffs_then_fraction_then_caps() {
+        let service = test_support::recommendation_service(FeatureFlags::default());
+
+        // The percentile cutoff (0.5) is stricter than min_score, then
+        // top_percentile halves the five survivors, not all ten candidates
+        let filtered = service.apply_advanced_filters(scored(10), Some(0.35), None, Some(0.5), Some(0.5), None);
+        assert_eq!(ids(&filtered), vec![1, 2, 3]);
+
+        // Here min_score is the stricter cutoff
+        let filtered = service.apply_advanced_filters(scored(10), Some(0.65), None, Some(0.5), Some(0.2), None);
+        assert_eq!(ids(&filtered), vec![1, 2]);
+
+        // top_k and limit both cap the count; the smaller wins
+        let filtered = service.apply_advanced_filters(scored(10), Some(0.0), Some(4), None, None, Some(6));
+        assert_eq!(ids(&filtered), vec![1, 2, 3, 4]);
+        let filtered = service.apply_advanced_filters(scored(10), None, Some(4), None, None, Some(2));
+        assert_eq!(ids(&filtered), vec![1, 2]);
+    }
 }
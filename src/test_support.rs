@@ -0,0 +1,134 @@
+//! Fixtures shared by unit tests.
+
+use crate::config::{
+    CurrencyConfig, DedupConfig, FeatureFlags, FreshnessConfig, LocationFalloffConfig, PropertyTypeTaxonomy,
+    RecommendationConfig, RetryConfig, ScoringWeights,
+};
+use crate::db::Repository;
+use crate::models::*;
+use crate::services::RecommendationService;
+use crate::utils::currency::CurrencyConverter;
+use chrono::{Duration, Utc};
+use sqlx::postgres::PgPoolOptions;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Central Algiers, where fixtures are located unless a test moves them.
+pub const ORIGIN: (f64, f64) = (36.7538, 3.0588);
+
+/// An available 3-room, 100 sqm apartment at `ORIGIN`, listed a year ago so
+/// the freshness boost does not apply.
+pub fn property(id: i32, price: f64) -> Property {
+    let listed_at = Utc::now() - Duration::days(365);
+    Property {
+        id,
+        address: format!("{} Rue Didouche Mourad", id),
+        location: Location { lat: ORIGIN.0, lon: ORIGIN.1 },
+        price,
+        area_sqm: 100,
+        property_type: "apartment".to_string(),
+        number_of_rooms: 3,
+        amenities: Vec::new(),
+        listed_at,
+        currency: None,
+        availability: PropertyAvailability::Available,
+        created_at: listed_at,
+        updated_at: listed_at,
+        deleted_at: None,
+    }
+}
+
+/// A preferred location named after its coordinates.
+pub fn location(lat: f64, lon: f64, radius_km: Option<f64>) -> NamedLocation {
+    NamedLocation {
+        name: format!("{:.4},{:.4}", lat, lon),
+        lat,
+        lon,
+        radius_km,
+        weight: None,
+    }
+}
+
+/// A contact looking for a 2+ room, 80-150 sqm apartment near `ORIGIN` with a
+/// budget of 100k-200k.
+pub fn contact(id: i32) -> Contact {
+    let now = Utc::now();
+    Contact {
+        id,
+        name: format!("Contact {}", id),
+        preferred_locations: vec![location(ORIGIN.0, ORIGIN.1, None)],
+        min_budget: 100_000.0,
+        max_budget: 200_000.0,
+        min_area_sqm: 80,
+        max_area_sqm: 150,
+        property_types: vec!["apartment".to_string()],
+        min_rooms: 2,
+        max_rooms: None,
+        required_amenities: Vec::new(),
+        email: None,
+        phone: None,
+        currency: None,
+        excluded_locations: Vec::new(),
+        excluded_property_types: Vec::new(),
+        created_at: now,
+        updated_at: now,
+        deleted_at: None,
+    }
+}
+
+/// One EUR is worth 150 base (DZD) units.
+pub fn currency_converter() -> CurrencyConverter {
+    CurrencyConverter::new(&CurrencyConfig {
+        base: "DZD".to_string(),
+        rates: HashMap::from([("EUR".to_string(), 150.0)]),
+    })
+}
+
+/// A repository over a pool that never connects. Needs a Tokio runtime.
+pub fn repository() -> Arc<Repository> {
+    let pool = PgPoolOptions::new()
+        .connect_lazy("postgresql://test@localhost/test")
+        .expect("lazy pool");
+    Arc::new(Repository::new(pool, RetryConfig { max_retries: 0, base_delay_ms: 0 }))
+}
+
+/// A service without a reachable database, for exercising pure scoring.
+pub fn recommendation_service(flags: FeatureFlags) -> RecommendationService {
+    let config = RecommendationConfig {
+        threshold: 0.3,
+        max_recommendations: 10,
+        cache_ttl_seconds: 60,
+        scoring_weights: ScoringWeights::default(),
+        min_quality_threshold: 0.0,
+        dedup: DedupConfig::default(),
+        freshness: FreshnessConfig::default(),
+        property_types: PropertyTypeTaxonomy::default(),
+        location_falloff: LocationFalloffConfig::default(),
+        slow_request_threshold_ms: 0,
+    };
+    RecommendationService::new(repository(), &config, 100, currency_converter(), flags)
+}
+
+/// A recommendation pairing the fixture contact and property with a fixed
+/// score; the explanation is a placeholder.
+pub fn recommendation(property: Property, score: f64) -> Recommendation {
+    Recommendation {
+        contact: contact(1),
+        property,
+        score,
+        explanation: RecommendationExplanation {
+            overall_score: score,
+            budget_match: BudgetMatch { is_within_budget: true, budget_utilization: 0.5, score },
+            location_match: LocationMatch { distance_km: 0.0, is_preferred_location: true, score },
+            property_type_match: true,
+            property_type_score: 1.0,
+            size_match: SizeMatch { rooms_match: true, area_match: true, score },
+            amenity_match: AmenityMatch::default(),
+            freshness: FreshnessBoost::default(),
+            price_per_sqm: None,
+            excluded: None,
+            reasons: Vec::new(),
+        },
+        created_at: Utc::now(),
+    }
+}
@@ -0,0 +1,71 @@
+use crate::config::CurrencyConfig;
+use crate::models::Recommendation;
+use std::collections::HashMap;
+
+/// Converts amounts between currencies using the configured static rates.
+/// Amounts without a currency are taken to be in the base currency.
+#[derive(Debug, Clone)]
+pub struct CurrencyConverter {
+    base: String,
+    rates: HashMap<String, f64>, // Units of base per one unit of the currency
+}
+
+impl CurrencyConverter {
+    pub fn new(config: &CurrencyConfig) -> Self {
+        let mut rates = config.rates.clone();
+        rates.insert(config.base.clone(), 1.0);
+
+        Self { base: config.base.clone(), rates }
+    }
+
+    /// ISO 4217 code of the base currency.
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    pub fn supports(&self, currency: &str) -> bool {
+        self.rates.contains_key(&currency.to_uppercase())
+    }
+
+    fn rate(&self, currency: Option<&str>) -> Option<f64> {
+        match currency {
+            Some(code) => self.rates.get(&code.to_uppercase()).copied(),
+            None => Some(1.0),
+        }
+    }
+
+    /// Returns `None` when either currency has no configured rate.
+    pub fn convert(&self, amount: f64, from: Option<&str>, to: Option<&str>) -> Option<f64> {
+        Some(amount * self.rate(from)? / self.rate(to)?)
+    }
+
+    /// Converts to the base currency. Returns `None` when `from` has no
+    /// configured rate, so callers skip or flag the amount instead of
+    /// comparing it unconverted.
+    pub fn to_base(&self, amount: f64, from: Option<&str>) -> Option<f64> {
+        self.convert(amount, from, None)
+    }
+
+    /// Re-expresses a recommendation's price and budget in `currency` for
+    /// display. Scores are unaffected.
+    pub fn localize(&self, recommendation: &mut Recommendation, currency: &str) {
+        let currency = currency.to_uppercase();
+
+        let property = &mut recommendation.property;
+        if let Some(price) = self.convert(property.price, property.currency.as_deref(), Some(&currency)) {
+            property.price = price;
+            property.currency = Some(currency.clone());
+        }
+
+        let contact = &mut recommendation.contact;
+        let from = contact.currency.as_deref();
+        if let (Some(min), Some(max)) = (
+            self.convert(contact.min_budget, from, Some(&currency)),
+            self.convert(contact.max_budget, from, Some(&currency)),
+        ) {
+            contact.min_budget = min;
+            contact.max_budget = max;
+            contact.currency = Some(currency);
+        }
+    }
+}
@@ -1,2 +1,3 @@
 pub mod scoring;
 pub mod pdf;
+pub mod currency;
@@ -1,5 +1,6 @@
 use printpdf::*;
 use crate::models::{Property, Contact, PropertyComparison, Recommendation};
+use crate::services::quote::Quote;
 use anyhow::Result;
 use chrono::Utc;
 use std::io::BufWriter;
@@ -143,6 +144,73 @@ pub fn generate_quote_pdf(property: &Property, contact: &Contact, additional_cos
     Ok(pdf_bytes)
 }
 
+pub fn generate_itemized_quote_pdf(quote: &Quote) -> Result<Vec<u8>> {
+    let (doc, page1, layer1) = PdfDocument::new("Property Quote", Mm(210.0), Mm(297.0), "Layer 1");
+    let current_layer = doc.get_page(page1).get_layer(layer1);
+
+    let font = doc.add_builtin_font(BuiltinFont::HelveticaBold)?;
+    let regular_font = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+
+    // Header
+    current_layer.use_text("PROPERTY QUOTE", 20.0, Mm(20.0), Mm(270.0), &font);
+    current_layer.use_text(format!("Quote Date: {} | Valid Until: {}",
+        quote.created_at.format("%Y-%m-%d"),
+        quote.expires_at.format("%Y-%m-%d")), 10.0, Mm(20.0), Mm(260.0), &regular_font);
+
+    // Property information
+    let mut y_position = 240.0;
+    current_layer.use_text("Property Details:", 14.0, Mm(20.0), Mm(y_position), &font);
+    y_position -= 10.0;
+    current_layer.use_text(format!("Address: {}", quote.property.address), 12.0, Mm(25.0), Mm(y_position), &regular_font);
+    y_position -= 8.0;
+    current_layer.use_text(format!("Property Type: {}", quote.property.property_type), 12.0, Mm(25.0), Mm(y_position), &regular_font);
+    y_position -= 8.0;
+    current_layer.use_text(format!("Area: {} sqm | Rooms: {}", quote.property.area_sqm, quote.property.number_of_rooms), 12.0, Mm(25.0), Mm(y_position), &regular_font);
+
+    // Itemized pricing
+    y_position -= 20.0;
+    current_layer.use_text("Pricing Breakdown:", 14.0, Mm(20.0), Mm(y_position), &font);
+    y_position -= 10.0;
+    current_layer.use_text(format!("Base Price: {:.2} {}", quote.base_price, quote.currency), 12.0, Mm(25.0), Mm(y_position), &regular_font);
+
+    if quote.discount_amount > 0.0 {
+        y_position -= 8.0;
+        current_layer.use_text(format!("Discount ({:.1}%): -{:.2} {}",
+            quote.discount_percentage, quote.discount_amount, quote.currency), 12.0, Mm(25.0), Mm(y_position), &regular_font);
+    }
+
+    y_position -= 12.0;
+    current_layer.use_text("Fees:", 12.0, Mm(25.0), Mm(y_position), &font);
+    for fee in &quote.fees {
+        y_position -= 8.0;
+        current_layer.use_text(format!("{}: {:.2} {}", fee.description, fee.amount, quote.currency), 11.0, Mm(30.0), Mm(y_position), &regular_font);
+    }
+
+    y_position -= 12.0;
+    current_layer.use_text("Taxes:", 12.0, Mm(25.0), Mm(y_position), &font);
+    for tax in &quote.taxes {
+        y_position -= 8.0;
+        current_layer.use_text(format!("{}: {:.2} {}", tax.description, tax.amount, quote.currency), 11.0, Mm(30.0), Mm(y_position), &regular_font);
+    }
+
+    y_position -= 15.0;
+    current_layer.use_text(format!("TOTAL: {:.2} {}", quote.total, quote.currency), 14.0, Mm(25.0), Mm(y_position), &font);
+
+    // Terms and conditions
+    y_position -= 25.0;
+    current_layer.use_text("Terms & Conditions:", 12.0, Mm(20.0), Mm(y_position), &font);
+    y_position -= 8.0;
+    current_layer.use_text("• This quote is valid for 30 days from the date of issue", 10.0, Mm(25.0), Mm(y_position), &regular_font);
+    y_position -= 6.0;
+    current_layer.use_text("• Final price subject to property inspection and legal review", 10.0, Mm(25.0), Mm(y_position), &regular_font);
+
+    let mut pdf_bytes = Vec::new();
+    let mut buf_writer = BufWriter::new(&mut pdf_bytes);
+    doc.save(&mut buf_writer)?;
+    drop(buf_writer); // Ensure buffer is flushed
+    Ok(pdf_bytes)
+}
+
 pub fn generate_recommendation_report_pdf(recommendations: &[Recommendation], property: &Property) -> Result<Vec<u8>> {
     let (doc, page1, layer1) = PdfDocument::new("Recommendation Report", Mm(210.0), Mm(297.0), "Layer 1");
     let current_layer = doc.get_page(page1).get_layer(layer1);
@@ -1,4 +1,9 @@
-use crate::models::{Contact, Property};
+use crate::config::{DedupConfig, FreshnessConfig, LocationFalloff, LocationFalloffConfig, PropertyTypeTaxonomy, ScoringWeights};
+use crate::models::{Contact, Property, Recommendation, RecommendationSort};
+use crate::utils::currency::CurrencyConverter;
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+use std::collections::HashMap;
 
 pub fn calculate_distance_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
     const EARTH_RADIUS_KM: f64 = 6371.0;
@@ -39,48 +44,107 @@ pub fn calculate_budget_score(property_price: f64, budget_min: f64, budget_max:
     }
 }
 
-pub fn calculate_location_score(property: &Property, contact: &Contact) -> f64 {
-    if contact.preferred_locations.is_empty() {
-        return 0.5; // Neutral score if no location preference
+/// Radius used for preferred locations that do not specify their own.
+pub const DEFAULT_LOCATION_RADIUS_KM: f64 = 15.0;
+
+/// Scores distance to a single preferred location using the configured curve.
+/// Every curve except `Linear` scores 0.5 at the radius when steepness is 1.0.
+fn distance_score(distance_km: f64, radius_km: f64, falloff: &LocationFalloffConfig) -> f64 {
+    let ratio = distance_km / radius_km * falloff.steepness;
+
+    match falloff.falloff {
+        LocationFalloff::Stepped => {
+            let scaled = ratio * DEFAULT_LOCATION_RADIUS_KM;
+            if scaled <= 5.0 {
+                1.0
+            } else if scaled <= 15.0 {
+                1.0 - (scaled - 5.0) / 10.0 * 0.5
+            } else if scaled <= 50.0 {
+                0.5 - (scaled - 15.0) / 35.0 * 0.4
+            } else {
+                0.1
+            }
+        }
+        LocationFalloff::Linear => (1.0 - ratio / 2.0).max(0.0),
+        LocationFalloff::Exponential => (-std::f64::consts::LN_2 * ratio).exp(),
+        LocationFalloff::Gaussian => (-std::f64::consts::LN_2 * ratio * ratio).exp(),
     }
+}
 
-    let mut best_score: f64 = 0.0;
-    
-    for preferred_location in &contact.preferred_locations {
-        let distance = calculate_distance_km(
-            property.location.lat, 
-            property.location.lon, 
-            preferred_location.lat, 
-            preferred_location.lon
-        );
-        
-        // Distance-based score (closer is better)
-        let distance_score = if distance <= 5.0 {
-            1.0
-        } else if distance <= 15.0 {
-            1.0 - (distance - 5.0) / 10.0 * 0.5
-        } else if distance <= 50.0 {
-            0.5 - (distance - 15.0) / 35.0 * 0.4
-        } else {
-            0.1
-        };
+/// Radius used for excluded locations that do not specify their own.
+pub const DEFAULT_EXCLUSION_RADIUS_KM: f64 = 1.0;
 
-        best_score = best_score.max(distance_score);
+/// Explains why a contact's exclusions rule a property out, or `None` if they do not.
+pub fn exclusion_reason(
+    property: &Property,
+    contact: &Contact,
+    taxonomy: &PropertyTypeTaxonomy,
+) -> Option<String> {
+    if let Some(excluded) = contact.excluded_property_types
+        .iter()
+        .find(|excluded| taxonomy.is_a(&property.property_type, excluded))
+    {
+        return Some(format!("Excluded property type: {}", excluded));
+    }
+
+    contact.excluded_locations
+        .iter()
+        .find(|area| {
+            calculate_distance_km(property.location.lat, property.location.lon, area.lat, area.lon)
+                <= area.radius_km.unwrap_or(DEFAULT_EXCLUSION_RADIUS_KM)
+        })
+        .map(|area| format!("Within excluded area: {}", area.name))
+}
+
+/// Combines every preferred location into one score. The best weighted match
+/// dominates; each further location the property is within radius of closes
+/// part of the remaining gap, so being near two preferred areas beats being
+/// near one without counting the property twice.
+pub fn calculate_location_score(
+    property: &Property,
+    contact: &Contact,
+    falloff: &LocationFalloffConfig,
+) -> f64 {
+    if contact.preferred_locations.is_empty() {
+        return 0.5; // Neutral score if no location preference
     }
 
-    best_score
+    let mut matches: Vec<f64> = contact.preferred_locations
+        .iter()
+        .map(|preferred_location| {
+            let distance = calculate_distance_km(
+                property.location.lat,
+                property.location.lon,
+                preferred_location.lat,
+                preferred_location.lon
+            );
+            let radius = preferred_location.radius_km.unwrap_or(DEFAULT_LOCATION_RADIUS_KM);
+            let weight = preferred_location.weight.unwrap_or(1.0);
+            weight * distance_score(distance, radius, falloff)
+        })
+        .collect();
+    matches.sort_by(|a, b| compare_scores_desc(*a, *b));
+
+    let best_score = matches[0];
+    let secondary = matches[1..].iter().filter(|score| **score >= 0.5);
+    secondary.fold(best_score, |score, other| score + (1.0 - score) * 0.2 * other)
 }
 
-pub fn calculate_property_type_score(property: &Property, contact: &Contact) -> f64 {
+/// Best taxonomy similarity between the property's type and any type the
+/// contact accepts, so related types earn partial credit.
+pub fn calculate_property_type_score(
+    property: &Property,
+    contact: &Contact,
+    taxonomy: &PropertyTypeTaxonomy,
+) -> f64 {
     if contact.property_types.is_empty() {
         return 0.5; // Neutral score if no type preference
     }
 
-    if contact.property_types.contains(&property.property_type) {
-        1.0
-    } else {
-        0.0
-    }
+    contact.property_types
+        .iter()
+        .map(|wanted| taxonomy.similarity(wanted, &property.property_type))
+        .fold(0.0, f64::max)
 }
 
 pub fn calculate_size_score(property: &Property, contact: &Contact) -> f64 {
@@ -90,6 +154,10 @@ pub fn calculate_size_score(property: &Property, contact: &Contact) -> f64 {
     // Room matching
     if property.number_of_rooms < contact.min_rooms {
         room_score = 0.1; // Too few rooms
+    } else if let Some(max_rooms) = contact.max_rooms.filter(|&max_rooms| property.number_of_rooms > max_rooms) {
+        // Each room over the limit costs more than extra area does; "no more" is usually firm
+        let extra_rooms = (property.number_of_rooms - max_rooms) as f64;
+        room_score = (1.0 - extra_rooms * 0.3).max(0.1);
     }
 
     // Area matching
@@ -104,18 +172,487 @@ pub fn calculate_size_score(property: &Property, contact: &Contact) -> f64 {
     (room_score + area_score) / 2.0
 }
 
+/// Splits a contact's required amenities into those the property offers and those it lacks.
+pub fn match_amenities(property: &Property, contact: &Contact) -> (Vec<String>, Vec<String>) {
+    contact.required_amenities.iter().cloned().partition(|required| {
+        property.amenities.iter().any(|amenity| amenity.eq_ignore_ascii_case(required))
+    })
+}
+
+pub fn calculate_amenity_score(property: &Property, contact: &Contact) -> f64 {
+    if contact.required_amenities.is_empty() {
+        return 1.0; // Nothing required, nothing to penalize
+    }
+
+    let (matched, _) = match_amenities(property, contact);
+    matched.len() as f64 / contact.required_amenities.len() as f64
+}
+
+/// Scales a weighted score by amenity coverage; a property missing every
+/// required amenity keeps half of its score rather than being excluded.
+pub fn apply_amenity_penalty(score: f64, amenity_score: f64) -> f64 {
+    score * (0.5 + 0.5 * amenity_score)
+}
+
 pub fn calculate_overall_score(
     budget_score: f64,
     location_score: f64,
     property_type_score: f64,
     size_score: f64,
-    budget_weight: f64,
-    location_weight: f64,
-    property_type_weight: f64,
-    size_weight: f64,
+    weights: &ScoringWeights,
 ) -> f64 {
-    budget_score * budget_weight
-        + location_score * location_weight
-        + property_type_score * property_type_weight
-        + size_score * size_weight
+    budget_score * weights.budget
+        + location_score * weights.location
+        + property_type_score * weights.property_type
+        + size_score * weights.size
+}
+
+/// Scores how recently a property was listed: 1.0 when just listed, falling
+/// linearly to 0.0 at the end of the freshness window.
+pub fn calculate_freshness_score(listed_at: DateTime<Utc>, now: DateTime<Utc>, config: &FreshnessConfig) -> f64 {
+    let age_days = (now - listed_at).num_seconds().max(0) as f64 / 86_400.0;
+    (1.0 - age_days / config.window_days).clamp(0.0, 1.0)
+}
+
+pub fn apply_freshness_boost(relevance: f64, freshness_score: f64, config: &FreshnessConfig) -> f64 {
+    (1.0 - config.weight) * relevance + config.weight * freshness_score
+}
+
+/// Orders scores from highest to lowest, treating NaN as lower than any number.
+pub fn compare_scores_desc(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => b.total_cmp(&a),
+    }
+}
+
+/// Sorts recommendations by descending score; ties are broken by property id
+/// and then contact id so repeated requests return the same order.
+pub fn sort_recommendations(recommendations: &mut [Recommendation]) {
+    recommendations.sort_by(|a, b| {
+        compare_scores_desc(a.score, b.score)
+            .then_with(|| a.property.id.cmp(&b.property.id))
+            .then_with(|| a.contact.id.cmp(&b.contact.id))
+    });
+}
+
+/// Reorders recommendations for display. Listings without a price per square
+/// metre sort last in either direction; ties fall back to score order.
+pub fn sort_recommendations_by(recommendations: &mut [Recommendation], sort_by: RecommendationSort) {
+    let descending = match sort_by {
+        RecommendationSort::Score => return sort_recommendations(recommendations),
+        RecommendationSort::PricePerSqmAsc => false,
+        RecommendationSort::PricePerSqmDesc => true,
+    };
+
+    recommendations.sort_by(|a, b| {
+        // The explanation's figure is in the base currency, so mixed-currency listings compare fairly
+        let by_price = match (a.explanation.price_per_sqm, b.explanation.price_per_sqm) {
+            (Some(x), Some(y)) if descending => y.total_cmp(&x),
+            (Some(x), Some(y)) => x.total_cmp(&y),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        by_price
+            .then_with(|| compare_scores_desc(a.score, b.score))
+            .then_with(|| a.property.id.cmp(&b.property.id))
+    });
+}
+
+/// Lowercases an address and collapses punctuation and whitespace so that
+/// "12 Rue Didouche-Mourad" and "12, rue didouche mourad" compare equal.
+pub fn normalize_address(address: &str) -> String {
+    address
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn within_tolerance(a: f64, b: f64, tolerance: f64) -> bool {
+    let scale = a.abs().max(b.abs());
+    scale == 0.0 || (a - b).abs() / scale <= tolerance
+}
+
+/// Prices are compared in the base currency, so a unit re-listed in another
+/// currency is still recognized. A price without a rate never matches.
+fn is_duplicate_listing(a: &Property, b: &Property, config: &DedupConfig, currency: &CurrencyConverter) -> bool {
+    let (Some(price_a), Some(price_b)) = (
+        currency.to_base(a.price, a.currency.as_deref()),
+        currency.to_base(b.price, b.currency.as_deref()),
+    ) else {
+        return false;
+    };
+    within_tolerance(price_a, price_b, config.tolerance)
+        && within_tolerance(a.area_sqm as f64, b.area_sqm as f64, config.tolerance)
+}
+
+/// Collapses re-listed properties, keeping the first (highest-scored) instance
+/// of each. Expects recommendations already sorted by descending score, so it
+/// only applies to ranked property lists: the contact and batch paths. Streams
+/// are unranked, and property-to-contacts results never repeat a property.
+pub fn dedup_recommendations(
+    recommendations: Vec<Recommendation>,
+    config: &DedupConfig,
+    currency: &CurrencyConverter,
+) -> Vec<Recommendation> {
+    if config.radius_meters <= 0.0 {
+        return recommendations;
+    }
+
+    // Bucket kept listings on a grid of radius-sized cells so each candidate
+    // is only compared with neighbours rather than every kept listing
+    let cell_degrees = config.radius_meters / 111_320.0;
+    let cell_of = |property: &Property| {
+        let x = property.location.lon * property.location.lat.to_radians().cos();
+        (
+            (property.location.lat / cell_degrees).floor() as i64,
+            (x / cell_degrees).floor() as i64,
+        )
+    };
+
+    let mut kept: Vec<Recommendation> = Vec::with_capacity(recommendations.len());
+    let mut by_address: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut by_cell: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+
+    for recommendation in recommendations {
+        let property = &recommendation.property;
+        let address = normalize_address(&property.address);
+        let (row, col) = cell_of(property);
+
+        let same_address = by_address.get(&address).into_iter().flatten();
+        let nearby = (-1..=1)
+            .flat_map(|dr| (-1..=1).map(move |dc| (row + dr, col + dc)))
+            .filter_map(|cell| by_cell.get(&cell))
+            .flatten()
+            .filter(|&&index| {
+                let other = &kept[index].property.location;
+                calculate_distance_km(property.location.lat, property.location.lon, other.lat, other.lon) * 1000.0
+                    <= config.radius_meters
+            });
+
+        let duplicate = same_address
+            .chain(nearby)
+            .any(|&index| is_duplicate_listing(property, &kept[index].property, config, currency));
+        if duplicate {
+            continue;
+        }
+
+        let index = kept.len();
+        if !address.is_empty() {
+            by_address.entry(address).or_default().push(index);
+        }
+        by_cell.entry((row, col)).or_default().push(index);
+        kept.push(recommendation);
+    }
+
+    kept
+}
+
+/// Number of items making up `fraction` of `len`, rounded up. A small epsilon
+/// keeps float noise (e.g. `(1.0 - 0.7) * 10.0 = 3.0000000000000004`) from
+/// adding an extra item.
+pub fn fraction_count(fraction: f64, len: usize) -> usize {
+    ((fraction * len as f64) - 1e-9).ceil().max(0.0) as usize
+}
+
+/// Score at the given percentile of a list sorted best first: 0.8 returns the
+/// lowest score in the top 20%. Returns `None` for an empty list or a NaN
+/// score so callers skip the cut rather than discard everything.
+pub fn percentile_cutoff(recommendations: &[Recommendation], percentile: f64) -> Option<f64> {
+    if recommendations.is_empty() {
+        return None;
+    }
+    let rank = fraction_count(1.0 - percentile, recommendations.len());
+    let index = rank.clamp(1, recommendations.len()) - 1;
+    let score = recommendations[index].score;
+    (!score.is_nan()).then_some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, ORIGIN};
+
+    fn ids(recommendations: &[Recommendation]) -> Vec<i32> {
+        recommendations.iter().map(|r| r.property.id).collect()
+    }
+
+    #[test]
+    fn nan_scores_compare_below_every_number() {
+        assert_eq!(compare_scores_desc(f64::NAN, 0.0), Ordering::Greater);
+        assert_eq!(compare_scores_desc(f64::NEG_INFINITY, f64::NAN), Ordering::Less);
+        assert_eq!(compare_scores_desc(f64::NAN, f64::NAN), Ordering::Equal);
+        assert_eq!(compare_scores_desc(0.9, 0.1), Ordering::Less);
+    }
+
+    #[test]
+    fn sorting_with_nan_scores_does_not_panic_and_puts_them_last() {
+        let mut recommendations = vec![
+            test_support::recommendation(test_support::property(1, 150_000.0), f64::NAN),
+            test_support::recommendation(test_support::property(2, 150_000.0), 0.4),
+            test_support::recommendation(test_support::property(3, 150_000.0), f64::NAN),
+            test_support::recommendation(test_support::property(4, 150_000.0), 0.9),
+            test_support::recommendation(test_support::property(5, 150_000.0), 0.0),
+        ];
+
+        sort_recommendations(&mut recommendations);
+
+        // NaNs tie with each other, so they fall back to property id order
+        assert_eq!(ids(&recommendations), vec![4, 2, 5, 1, 3]);
+    }
+
+    fn ranked(properties: Vec<Property>) -> Vec<Recommendation> {
+        let count = properties.len() as f64;
+        properties
+            .into_iter()
+            .enumerate()
+            .map(|(rank, property)| test_support::recommendation(property, 1.0 - rank as f64 / count))
+            .collect()
+    }
+
+    #[test]
+    fn dedup_keeps_the_best_instance_of_a_relisted_unit() {
+        let original = test_support::property(1, 150_000.0);
+        let mut relisted = test_support::property(2, 151_000.0);
+        relisted.address = "1, rue Didouche-Mourad".to_string();
+        relisted.location.lat += 0.01; // ~1km away, so only the address matches
+        let next_door = test_support::property(3, 150_000.0);
+
+        let kept = dedup_recommendations(
+            ranked(vec![original, relisted, next_door]),
+            &DedupConfig::default(),
+            &test_support::currency_converter(),
+        );
+
+        // Property 2 matches by address, property 3 by location despite its own address
+        assert_eq!(ids(&kept), vec![1]);
+    }
+
+    #[test]
+    fn dedup_keeps_listings_outside_the_tolerance() {
+        let original = test_support::property(1, 150_000.0);
+        let pricier = test_support::property(2, 180_000.0);
+        let mut larger = test_support::property(3, 150_000.0);
+        larger.area_sqm = 140;
+
+        let kept = dedup_recommendations(
+            ranked(vec![original, pricier, larger]),
+            &DedupConfig::default(),
+            &test_support::currency_converter(),
+        );
+        assert_eq!(ids(&kept), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn dedup_compares_prices_across_currencies() {
+        let in_dinars = test_support::property(1, 150_000.0);
+        let mut in_euros = test_support::property(2, 1_000.0);
+        in_euros.currency = Some("EUR".to_string());
+        let mut cheap_in_dinars = test_support::property(3, 1_000.0);
+        cheap_in_dinars.address = in_dinars.address.clone();
+
+        let kept = dedup_recommendations(
+            ranked(vec![in_dinars, in_euros, cheap_in_dinars]),
+            &DedupConfig::default(),
+            &test_support::currency_converter(),
+        );
+
+        // 1,000 EUR is 150,000 DZD; 1,000 DZD is a different listing
+        assert_eq!(ids(&kept), vec![1, 3]);
+    }
+
+    #[test]
+    fn dedup_is_disabled_by_a_zero_radius() {
+        let config = DedupConfig { radius_meters: 0.0, ..DedupConfig::default() };
+        let kept = dedup_recommendations(
+            ranked(vec![test_support::property(1, 150_000.0), test_support::property(2, 150_000.0)]),
+            &config,
+            &test_support::currency_converter(),
+        );
+        assert_eq!(ids(&kept), vec![1, 2]);
+    }
+
+    /// Degrees of latitude spanning `km`, for placing locations at known distances.
+    fn lat_offset(km: f64) -> f64 {
+        km / (6371.0 * std::f64::consts::PI / 180.0)
+    }
+
+    fn linear() -> LocationFalloffConfig {
+        LocationFalloffConfig { falloff: LocationFalloff::Linear, steepness: 1.0 }
+    }
+
+    fn contact_near(locations: &[(f64, f64)]) -> Contact {
+        let mut contact = test_support::contact(1);
+        contact.preferred_locations = locations
+            .iter()
+            .map(|&(km, weight)| {
+                let mut location = test_support::location(ORIGIN.0 + lat_offset(km), ORIGIN.1, Some(10.0));
+                location.weight = Some(weight);
+                location
+            })
+            .collect();
+        contact
+    }
+
+    #[test]
+    fn location_score_for_a_single_location() {
+        let property = test_support::property(1, 150_000.0);
+
+        let at_location = calculate_location_score(&property, &contact_near(&[(0.0, 1.0)]), &linear());
+        assert!((at_location - 1.0).abs() < 1e-9);
+
+        // Halfway to the radius on the linear curve
+        let five_km = calculate_location_score(&property, &contact_near(&[(5.0, 1.0)]), &linear());
+        assert!((five_km - 0.75).abs() < 1e-6);
+
+        // A location's weight scales its score
+        let weighted = calculate_location_score(&property, &contact_near(&[(5.0, 0.5)]), &linear());
+        assert!((weighted - 0.375).abs() < 1e-6);
+
+        let mut no_preference = test_support::contact(1);
+        no_preference.preferred_locations.clear();
+        assert_eq!(calculate_location_score(&property, &no_preference, &linear()), 0.5);
+    }
+
+    #[test]
+    fn disjoint_locations_score_like_the_nearest_one() {
+        let property = test_support::property(1, 150_000.0);
+
+        // The second location is 100km away, far outside its radius
+        let score = calculate_location_score(&property, &contact_near(&[(5.0, 1.0), (-100.0, 1.0)]), &linear());
+        assert!((score - 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn overlapping_locations_add_a_bounded_bonus() {
+        let property = test_support::property(1, 150_000.0);
+
+        // Two locations 5km either side: the second closes 20% of the gap, scaled by its score
+        let score = calculate_location_score(&property, &contact_near(&[(5.0, 1.0), (-5.0, 1.0)]), &linear());
+        assert!((score - (0.75 + 0.25 * 0.2 * 0.75)).abs() < 1e-6);
+
+        let many = calculate_location_score(
+            &property,
+            &contact_near(&[(0.0, 1.0), (1.0, 1.0), (-1.0, 1.0), (2.0, 1.0)]),
+            &linear(),
+        );
+        assert!(many <= 1.0);
+    }
+
+    #[test]
+    fn falloff_curves_at_half_and_full_radius() {
+        let curves = [
+            (LocationFalloff::Stepped, 0.875, 0.5),
+            (LocationFalloff::Linear, 0.75, 0.5),
+            (LocationFalloff::Exponential, std::f64::consts::FRAC_1_SQRT_2, 0.5),
+            (LocationFalloff::Gaussian, 0.5f64.powf(0.25), 0.5),
+        ];
+
+        for (falloff, at_half, at_radius) in curves {
+            let config = LocationFalloffConfig { falloff, steepness: 1.0 };
+            let half = distance_score(5.0, 10.0, &config);
+            let full = distance_score(10.0, 10.0, &config);
+            assert!((half - at_half).abs() < 1e-9, "{:?} at half radius: {}", falloff, half);
+            assert!((full - at_radius).abs() < 1e-9, "{:?} at radius: {}", falloff, full);
+        }
+    }
+
+    #[test]
+    fn steepness_scales_distance() {
+        let steep = LocationFalloffConfig { falloff: LocationFalloff::Exponential, steepness: 2.0 };
+        let gentle = LocationFalloffConfig { falloff: LocationFalloff::Exponential, steepness: 1.0 };
+        assert!((distance_score(5.0, 10.0, &steep) - distance_score(10.0, 10.0, &gentle)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn exclusion_reason_covers_types_subtypes_and_areas() {
+        let taxonomy = PropertyTypeTaxonomy::default();
+        let mut studio = test_support::property(1, 150_000.0);
+        studio.property_type = "studio".to_string();
+
+        let mut contact = test_support::contact(1);
+        assert_eq!(exclusion_reason(&studio, &contact, &taxonomy), None);
+
+        // Excluding a parent type also excludes its subtypes
+        contact.excluded_property_types = vec!["apartment".to_string()];
+        assert_eq!(
+            exclusion_reason(&studio, &contact, &taxonomy).as_deref(),
+            Some("Excluded property type: apartment")
+        );
+
+        // Areas use their own radius, defaulting to 1km
+        contact.excluded_property_types.clear();
+        let mut area = test_support::location(ORIGIN.0 + lat_offset(0.8), ORIGIN.1, None);
+        area.name = "Bab El Oued".to_string();
+        contact.excluded_locations = vec![area];
+        assert_eq!(
+            exclusion_reason(&studio, &contact, &taxonomy).as_deref(),
+            Some("Within excluded area: Bab El Oued")
+        );
+        contact.excluded_locations[0].radius_km = Some(0.5);
+        assert_eq!(exclusion_reason(&studio, &contact, &taxonomy), None);
+    }
+
+    fn scored(scores: &[f64]) -> Vec<Recommendation> {
+        scores
+            .iter()
+            .enumerate()
+            .map(|(index, &score)| test_support::recommendation(test_support::property(index as i32 + 1, 150_000.0), score))
+            .collect()
+    }
+
+    #[test]
+    fn fraction_count_rounds_up_without_float_noise() {
+        assert_eq!(fraction_count(0.0, 10), 0);
+        assert_eq!(fraction_count(1.0, 10), 10);
+        assert_eq!(fraction_count(0.25, 10), 3);
+        assert_eq!(fraction_count(1.0 - 0.7, 10), 3);
+        assert_eq!(fraction_count(0.01, 10), 1);
+        assert_eq!(fraction_count(0.5, 0), 0);
+    }
+
+    #[test]
+    fn percentile_cutoff_at_the_extremes() {
+        let recommendations = scored(&[0.9, 0.7, 0.5, 0.3, 0.1]);
+
+        // p=0 keeps everything, p=1 keeps only the best
+        assert_eq!(percentile_cutoff(&recommendations, 0.0), Some(0.1));
+        assert_eq!(percentile_cutoff(&recommendations, 1.0), Some(0.9));
+        assert_eq!(percentile_cutoff(&recommendations, 0.6), Some(0.7));
+        assert_eq!(percentile_cutoff(&[], 0.5), None);
+    }
+
+    #[test]
+    fn percentile_cutoff_with_ties_and_nan() {
+        let ties = scored(&[0.9, 0.8, 0.8, 0.8, 0.5]);
+        let cutoff = percentile_cutoff(&ties, 0.6).unwrap();
+        assert_eq!(cutoff, 0.8);
+        // Every tie with the cutoff score survives a `>=` cut
+        assert_eq!(ties.iter().filter(|r| r.score >= cutoff).count(), 4);
+
+        let with_nan = scored(&[0.9, f64::NAN]);
+        assert_eq!(percentile_cutoff(&with_nan, 0.0), None);
+    }
+
+    #[test]
+    fn rooms_over_the_maximum_are_penalized() {
+        let mut contact = test_support::contact(1);
+        let mut four_rooms = test_support::property(1, 150_000.0);
+        four_rooms.number_of_rooms = 4;
+
+        assert_eq!(calculate_size_score(&four_rooms, &contact), 1.0);
+
+        contact.max_rooms = Some(3);
+        let penalized = calculate_size_score(&four_rooms, &contact);
+        assert!(penalized < 1.0, "4 rooms against max_rooms=3 scored {}", penalized);
+        assert!(!contact.wants_rooms(four_rooms.number_of_rooms));
+
+        four_rooms.number_of_rooms = 3;
+        assert_eq!(calculate_size_score(&four_rooms, &contact), 1.0);
+    }
 }